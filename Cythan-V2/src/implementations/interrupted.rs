@@ -13,7 +13,8 @@ pub struct InterruptedCythan {
     pub base: u8,
     pub interrupt_place: usize,
     pub print_provider: Box<dyn Fn(u8)>,
-    pub input_provider: Box<dyn Fn() -> u8>,
+    /// Gives the next character of the input, `None` once it is over
+    pub input_provider: Box<dyn Fn() -> Option<u8>>,
 }
 
 impl std::fmt::Display for InterruptedCythan {
@@ -29,7 +30,7 @@ impl InterruptedCythan {
         base: u8,
         interrupt_place: usize,
         print_provider: impl Fn(u8) + 'static,
-        input_provider: impl Fn() -> u8 + 'static,
+        input_provider: impl Fn() -> Option<u8> + 'static,
     ) -> Self {
         Self {
             cases,
@@ -45,7 +46,7 @@ impl InterruptedCythan {
             base,
             interrupt_place,
             |a| print!("{}", a as char),
-            || std::io::stdin().bytes().next().unwrap().unwrap(),
+            || std::io::stdin().bytes().next().and_then(|x| x.ok()),
         )
     }
 }
@@ -104,7 +105,9 @@ impl Cythan for InterruptedCythan {
             if value == 2 {
                 // println!("INPUT");
                 //let o: u8 = std::io::stdin().bytes().next().unwrap().unwrap();
-                let mut o: u8 = (self.input_provider)();
+                let input = (self.input_provider)();
+                // The end of the input reads as 0
+                let mut o: u8 = input.unwrap_or(0);
                 // let a = o % 2_u64.pow(self.base as u32) as u8;
                 // let b = o / 2_u64.pow(self.base as u32) as u8;
                 // println!("vals:{} {}",a,b);
@@ -117,6 +120,8 @@ impl Cythan for InterruptedCythan {
                 // println!("o={}",o);
                 // self.set_value(self.interrupt_place + 1, b as usize);
                 // self.set_value(self.interrupt_place + 2, a as usize);
+                // The register after the character is 1 if one was read and 0 once the input is over
+                self.set_value(self.interrupt_place + 8_usize / (self.base as usize) + 1, input.is_some() as usize);
             }
         }
         if self.cases.len() <= index {
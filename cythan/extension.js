@@ -0,0 +1,21 @@
+const { workspace } = require('vscode');
+const { LanguageClient } = require('vscode-languageclient/node');
+
+let client;
+
+function activate(context) {
+    const command = workspace.getConfiguration('cythan').get('serverPath') || 'cyc';
+    client = new LanguageClient(
+        'cythan',
+        'CH2L language server',
+        { command, args: ['lsp'] },
+        { documentSelector: [{ scheme: 'file', language: 'cythan' }] }
+    );
+    context.subscriptions.push(client.start());
+}
+
+function deactivate() {
+    return client ? client.stop() : undefined;
+}
+
+module.exports = { activate, deactivate };
@@ -15,12 +15,13 @@ pub struct Context {
 
 #[derive(Debug, Clone)]
 pub enum CompilableInstruction {
-    Copy(Var, AsmValue), // to, from - from isn't mutated
-    Increment(Var),      // in, in is mutated
-    Decrement(Var),      // in, in is mutated
-    Jump(Label),         // Goto a label
-    Label(Label),        // Defines a label
-    If0(Var, Label),     // Jumps to the label if the thing is equals to 0
+    Copy(Var, AsmValue),     // to, from - from isn't mutated
+    Increment(Var),          // in, in is mutated
+    Decrement(Var),          // in, in is mutated
+    Jump(Label),             // Goto a label
+    Label(Label),            // Defines a label
+    If0(Var, Label),         // Jumps to the label if the thing is equals to 0
+    Switch(Var, Vec<Label>), // Jumps to the label at the index of the value
     Stop,
     ReadRegister(Var, Number),
     WriteRegister(Number, AsmValue),
@@ -64,6 +65,17 @@ impl CompilableInstruction {
                 Self::check_compile_var(a, template, ctx);
                 template.add_code(Cow::Owned(format!("if_0({} {})", a, b)))
             }
+            CompilableInstruction::Switch(a, b) => {
+                Self::check_compile_var(a, template, ctx);
+                template.add_code(Cow::Owned(format!(
+                    "switch({} {})",
+                    a,
+                    b.iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )))
+            }
             CompilableInstruction::Stop => template.add_code(Cow::Borrowed("stop")),
             CompilableInstruction::ReadRegister(a, b) => {
                 template.add_code(Cow::Owned(format!("'#int_{} {}", b.0, a)));
@@ -98,6 +110,15 @@ impl Display for CompilableInstruction {
             CompilableInstruction::Jump(a) => write!(f, "jmp {}", a),
             CompilableInstruction::Label(a) => write!(f, "{}", a),
             CompilableInstruction::If0(a, b) => write!(f, "if ${} {}", a.0, b),
+            CompilableInstruction::Switch(a, b) => write!(
+                f,
+                "switch ${} {}",
+                a.0,
+                b.iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
             CompilableInstruction::Stop => write!(f, "stop"),
             CompilableInstruction::ReadRegister(a, b) => write!(f, "${} = @{}", a.0, b.0),
             CompilableInstruction::WriteRegister(a, b) => write!(
@@ -124,6 +145,8 @@ pub enum LabelType {
     IfStart,
     ElseStart,
     IfEnd,
+    SwitchCase,
+    SwitchEnd,
 }
 
 impl Display for LabelType {
@@ -138,6 +161,8 @@ impl Display for LabelType {
                 LabelType::IfStart => 'D',
                 LabelType::ElseStart => 'E',
                 LabelType::IfEnd => 'F',
+                LabelType::SwitchCase => 'G',
+                LabelType::SwitchEnd => 'H',
             }
         )
     }
@@ -217,15 +242,15 @@ pub fn opt_asm(input: Vec<CompilableInstruction>) -> Vec<CompilableInstruction>
     if input.is_empty() {
         return vec![];
     }
-    std::fs::write(
+    // Only a help to debug the optimizer, `target` may not exist
+    let _ = std::fs::write(
         "target/before_opt.asm",
         input
             .iter()
             .map(|x| x.to_string())
             .collect::<Vec<_>>()
             .join("\n"),
-    )
-    .unwrap();
+    );
     let in_count = input.len();
     let mut out = Vec::new();
     let mut label_map: HashMap<Label, Label> = HashMap::new();
@@ -248,6 +273,14 @@ pub fn opt_asm(input: Vec<CompilableInstruction>) -> Vec<CompilableInstruction>
             out.push(el);
             continue;
         }
+        // Like jumps, switches never continue to the next instruction
+        if let CompilableInstruction::Switch(..) = &el {
+            if !in_jump {
+                out.push(el);
+            }
+            in_jump = true;
+            continue;
+        }
         if in_jump
             && matches!(
                 &el,
@@ -262,14 +295,13 @@ pub fn opt_asm(input: Vec<CompilableInstruction>) -> Vec<CompilableInstruction>
         out.push(el);
     }
     remap(&mut out, &label_map);
-    std::fs::write(
+    let _ = std::fs::write(
         "target/after_opt.asm",
         out.iter()
             .map(|x| x.to_string())
             .collect::<Vec<_>>()
             .join("\n"),
-    )
-    .unwrap();
+    );
     println!(
         "Optimized from {} ASM instructions to {} ASM instructions",
         in_count,
@@ -286,6 +318,9 @@ fn remap(asm: &mut [CompilableInstruction], amap: &HashMap<Label, Label>) {
         {
             *a = update(a, amap);
         }
+        if let CompilableInstruction::Switch(_, a) = i {
+            a.iter_mut().for_each(|a| *a = update(a, amap));
+        }
     });
 }
 
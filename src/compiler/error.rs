@@ -19,6 +19,7 @@ pub enum CErrorType {
     ExpectedBlock,
     FieldNotFound(String, String),
     FileNotFound(String),
+    NoBundledStd(u8),
     ParseFileError(Error<Rule>),
     InvalidNumber,
     InvalidBreakOrContinue,
@@ -26,6 +27,34 @@ pub enum CErrorType {
     WrongNumberOfArgument(usize),
     FunctionCallDoesntReturnValue,
     InternalCompilerError(String),
+    CircularImport(Vec<String>),
+    UnknownArgument(String),
+    DuplicateCase(String),
+    ArrayUsedAsVariableInInvalidContext,
+    ExpectedArray,
+    IndexOutOfBounds(usize, usize),
+    StringUsedAsVariableInInvalidContext,
+    InvalidEscape(String),
+    NonAsciiCharacter(char),
+    IncompatibleAssignment,
+    InvalidAssociativity(String),
+    NoMatchingOverload(String, usize, Vec<String>),
+    AmbiguousOverload(String, Vec<String>),
+    MissingArgument(String),
+    ArgumentAlreadyProvided(String),
+    InvalidVariadic,
+    ListUsedAsVariableInInvalidContext,
+    ExpectedList,
+    NotConstant(String),
+    FunctionUsedAsVariableInInvalidContext,
+    AssertionFailed,
+    TooManyAsserts,
+    DuplicateTest(String),
+    LoopNotFound(String),
+    InvalidReturn,
+    DigitDoesntFit(u8),
+    ValueDoesntFit(usize, u8),
+    ComputedFieldAssignment,
 }
 
 impl Display for CErrorType {
@@ -54,10 +83,89 @@ impl Display for CErrorType {
                 "Can't read `{}` file. Ensure that the path is correct",
                 b
             ),
-            Self::ParseFileError(_) => unreachable!(),
+            Self::NoBundledStd(a) => write!(
+                f,
+                "No standard library is bundled for the base {}. Give one with the `-I` option",
+                a
+            ),
+            Self::ParseFileError(a) => write!(f, "{}", a.variant.message()),
             Self::InternalCompilerError(a) => write!(f,"This error originated from the CythanV3 compiler and should be reported on https://github.com/Cythan-Project/cythan-high-level-compiler\n\
                     You should include your source code and the following error in the report.\n\
                     {}",a),
+            Self::CircularImport(a) => write!(f, "Circular import: {}", a.join(" -> ")),
+            Self::UnknownArgument(a) => write!(f, "Unknown argument `{}`", a),
+            Self::DuplicateCase(a) => write!(f, "Case `{}` is matched more than once", a),
+            Self::ArrayUsedAsVariableInInvalidContext => {
+                write!(f, "Array used as a variable in invalid context")
+            }
+            Self::ExpectedArray => write!(f, "Expected array"),
+            Self::NoMatchingOverload(a, b, c) => write!(
+                f,
+                "No definition of `{}` takes {} arguments, candidates are: {}",
+                a,
+                b,
+                c.join(", ")
+            ),
+            Self::AmbiguousOverload(a, b) => write!(
+                f,
+                "The call to `{}` matches several definitions: {}",
+                a,
+                b.join(", ")
+            ),
+            Self::MissingArgument(a) => write!(f, "Missing argument `{}`", a),
+            Self::ArgumentAlreadyProvided(a) => {
+                write!(f, "Argument `{}` is already provided", a)
+            }
+            Self::InvalidVariadic => write!(
+                f,
+                "Only the last argument can be variadic and it can't have a default value"
+            ),
+            Self::ListUsedAsVariableInInvalidContext => {
+                write!(f, "List used as a variable in invalid context")
+            }
+            Self::ExpectedList => write!(f, "Expected list"),
+            Self::FunctionUsedAsVariableInInvalidContext => {
+                write!(f, "Function used as a variable in invalid context")
+            }
+            Self::AssertionFailed => write!(f, "Assertion failed"),
+            Self::TooManyAsserts => write!(f, "A program can't have more than 254 asserts"),
+            Self::DuplicateTest(a) => write!(f, "The test `{}` is already defined", a),
+            Self::LoopNotFound(a) => write!(f, "Loop `{}` not found", a),
+            Self::InvalidReturn => write!(f, "Can't return outside of a function"),
+            Self::ComputedFieldAssignment => {
+                write!(f, "A `$` field is computed when it is used, it can't be set")
+            }
+            Self::ValueDoesntFit(a, b) => write!(
+                f,
+                "The value {} doesn't fit in a cell, the greatest value in base {} is {}",
+                a,
+                b,
+                (1_u32 << b) - 1
+            ),
+            Self::DigitDoesntFit(a) => write!(
+                f,
+                "A digit doesn't fit in a cell in base {}, the base has to be at least 4",
+                a
+            ),
+            Self::NotConstant(a) => write!(f, "The value can't be computed when compiling, {}", a),
+            Self::InvalidAssociativity(a) => {
+                write!(f, "Expected `left` or `right`, found `{}`", a)
+            }
+            Self::IncompatibleAssignment => {
+                write!(f, "The value doesn't have the same type as the target")
+            }
+            Self::StringUsedAsVariableInInvalidContext => {
+                write!(f, "String used as a variable in invalid context")
+            }
+            Self::InvalidEscape(a) => write!(f, "Invalid escape `\\{}`", a),
+            Self::NonAsciiCharacter(a) => {
+                write!(f, "Only ASCII characters can be used in strings, found `{}`", a)
+            }
+            Self::IndexOutOfBounds(a, b) => write!(
+                f,
+                "Index {} is out of bounds for an array of length {}",
+                a, b
+            ),
             CErrorType::StructUsedAsVariableInInvalidContext(a) => write!(f,"Struct `{}` used as a variable in invalid context",a),
         }
     }
@@ -0,0 +1,161 @@
+use crate::compiler::{
+    asm::{AsmValue, Number, Var},
+    error::{CError, CErrorType},
+    mir::{Mir, MirCodeBlock},
+    parser::{expression::Expression, function_call::FunctionCall},
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::{cell_number, CVariable},
+};
+
+use super::{fn_assert::ASSERT_FAILED, fn_print_str::encode};
+
+/// The index written in the registers after the register 0 when the program stopped on an index
+/// out of bounds, the register 0 is then `ASSERT_FAILED`. No assert has this index and `exit`
+/// writes `NO_ASSERT` there, so no exit code can be taken for it.
+pub const OUT_OF_BOUNDS: u8 = 254;
+
+/// `array(<number>)` allocates the given number of cells, all set to 0.
+pub fn ARRAY(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 1 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(1),
+        ));
+    }
+    let length = fc.arguments[0].as_number(ss, state, false)?;
+    let cells: Vec<usize> = (0..length.0).map(|_| state.count()).collect();
+    for i in &cells {
+        state
+            .instructions
+            .push(Mir::Copy(Var(*i), AsmValue::Number(Number(0))));
+    }
+    Ok(Some(CVariable::Array(vec![fc.span.clone()], cells)))
+}
+
+/// `get(<array>, <index>)` returns the element at the index.
+pub fn GET(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 2 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(2),
+        ));
+    }
+    let cells = get_array(state, ss, &fc.arguments[0])?;
+    let out = match index(state, ss, &fc.arguments[1], &cells)? {
+        Ok(e) => e,
+        Err(index) => {
+            let out = Var(state.count());
+            let cases = cells
+                .iter()
+                .map(|x| MirCodeBlock(vec![Mir::Copy(out.clone(), AsmValue::Var(Var(*x)))]))
+                .collect();
+            state.instructions.push(dispatch(state, index, cases));
+            out.0
+        }
+    };
+    Ok(Some(CVariable::Value(vec![fc.span.clone()], out)))
+}
+
+/// `set(<array>, <index>, <value>)` sets the element at the index.
+pub fn set_element(state: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<()> {
+    let cells = get_array(state, ss, &fc.arguments[0])?;
+    let target = index(state, ss, &fc.arguments[1], &cells)?;
+    let value = fc.arguments[2].get_asm_value(ss, state, false)?;
+    let mir = match target {
+        Ok(e) => Mir::Copy(Var(e), value),
+        Err(index) => {
+            let cases = cells
+                .iter()
+                .map(|x| MirCodeBlock(vec![Mir::Copy(Var(*x), value.clone())]))
+                .collect();
+            dispatch(state, index, cases)
+        }
+    };
+    state.instructions.push(mir);
+    Ok(())
+}
+
+/// `len(<array or list>)` returns the number of elements as a constant.
+pub fn LEN(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 1 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(1),
+        ));
+    }
+    let length = match fc.arguments[0].get_value(ss, state, false)?.unroll(state)? {
+        Some(CVariable::List(_, e)) => e.len(),
+        Some(CVariable::Array(_, e)) => e.len(),
+        Some(e) => return Err(CError(e.get_span().to_vec(), CErrorType::ExpectedArray)),
+        None => {
+            return Err(CError(
+                vec![fc.arguments[0].get_span().clone()],
+                CErrorType::ExpectedArray,
+            ))
+        }
+    };
+    Ok(Some(cell_number(state, &fc.span, length)?))
+}
+
+fn get_array(state: &mut State, ss: &mut ScopedState, expr: &Expression) -> Result<Vec<usize>> {
+    match expr.get_value(ss, state, false)?.unroll(state)? {
+        Some(CVariable::Array(_, e)) => Ok(e),
+        Some(e) => Err(CError(e.get_span().to_vec(), CErrorType::ExpectedArray)),
+        None => Err(CError(
+            vec![expr.get_span().clone()],
+            CErrorType::ExpectedArray,
+        )),
+    }
+}
+
+/// Returns the cell when the index is known at compile time, or the variable holding it.
+fn index(
+    state: &mut State,
+    ss: &mut ScopedState,
+    expr: &Expression,
+    cells: &[usize],
+) -> Result<std::result::Result<usize, Var>> {
+    match expr.get_asm_value(ss, state, false)? {
+        AsmValue::Number(e) => cells.get(e.0 as usize).map(|x| Ok(*x)).ok_or_else(|| {
+            CError(
+                vec![expr.get_span().clone()],
+                CErrorType::IndexOutOfBounds(e.0 as usize, cells.len()),
+            )
+        }),
+        AsmValue::Var(e) => Ok(Err(e)),
+    }
+}
+
+/// Jumps to the case of the index, the program stops with `OUT_OF_BOUNDS` in the registers if the
+/// index is out of bounds.
+fn dispatch(state: &State, index: Var, mut cases: Vec<MirCodeBlock>) -> Mir {
+    let out_of_bounds = cases.len();
+    let mut stop: Vec<Mir> = encode(OUT_OF_BOUNDS, state.base)
+        .into_iter()
+        .map(|(register, digit)| Mir::WriteRegister(register, AsmValue::Number(digit)))
+        .collect();
+    stop.push(Mir::WriteRegister(
+        Number(0),
+        AsmValue::Number(Number(ASSERT_FAILED)),
+    ));
+    stop.push(Mir::Stop);
+    cases.push(MirCodeBlock(stop));
+    let table = (0..2_usize.pow(state.base as u32))
+        .map(|x| x.min(out_of_bounds))
+        .collect();
+    Mir::Switch(index, table, cases)
+}
@@ -0,0 +1,85 @@
+use std::convert::TryFrom;
+
+use crate::compiler::{
+    asm::{AsmValue, Number},
+    error::{CError, CErrorType, CSpan},
+    mir::{Mir, MirCodeBlock},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+use super::{fn_array::OUT_OF_BOUNDS, fn_cmp::EQ, fn_print_str::encode};
+
+/// The value of the register 0 when the program stopped on a failed assert, the index of the
+/// assert is in the next registers (like a printed character). An index out of bounds stops the
+/// program the same way with `OUT_OF_BOUNDS` as index.
+pub const ASSERT_FAILED: u8 = 3;
+
+/// The index `exit` writes in the registers, no assert has it so `exit(3)` isn't taken for a
+/// failed assert.
+pub const NO_ASSERT: u8 = 255;
+
+/// `assert(<value>)` stops the program if the value isn't 0, like the conditions of `if0`.
+pub fn ASSERT(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 1 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(1),
+        ));
+    }
+    let value = fc.arguments[0].get_asm_value(ss, state, false)?;
+    check(state, value, &fc.span)
+}
+
+/// `assert_eq(<value>, <value>)` stops the program if the values are different.
+pub fn ASSERT_EQ(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 2 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(2),
+        ));
+    }
+    let value = match EQ(state, ss, fc)? {
+        Some(e) => e.to_asm(state)?,
+        None => unreachable!("Comparisons always have a value"),
+    };
+    check(state, value, &fc.span)
+}
+
+fn check(state: &mut State, value: AsmValue, span: &CSpan) -> Result<Option<CVariable>> {
+    let index = u8::try_from(state.asserts.len())
+        .ok()
+        .filter(|x| *x < OUT_OF_BOUNDS)
+        .ok_or_else(|| CError(vec![span.clone()], CErrorType::TooManyAsserts))?;
+    state.asserts.push(span.clone());
+    let mut failure: Vec<Mir> = encode(index, state.base)
+        .into_iter()
+        .map(|(register, digit)| Mir::WriteRegister(register, AsmValue::Number(digit)))
+        .collect();
+    failure.push(Mir::WriteRegister(
+        Number(0),
+        AsmValue::Number(Number(ASSERT_FAILED)),
+    ));
+    failure.push(Mir::Stop);
+    match value {
+        AsmValue::Number(Number(0)) => (),
+        AsmValue::Number(_) => state.instructions.0.extend(failure),
+        AsmValue::Var(e) => {
+            state
+                .instructions
+                .push(Mir::If0(e, MirCodeBlock(vec![]), MirCodeBlock(failure)))
+        }
+    }
+    Ok(None)
+}
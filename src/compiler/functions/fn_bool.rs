@@ -0,0 +1,124 @@
+//! Boolean logic using the `if0` convention: 0 is true and any other value is false.
+//! The results are always 0 or 1.
+
+use crate::compiler::{
+    asm::{AsmValue, Number, Var},
+    error::{CError, CErrorType},
+    mir::{Mir, MirCodeBlock},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+pub fn AND(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    let operands = operands(state, ss, fc, 2)?;
+    Ok(Some(truth_table(state, fc, operands, |x| x[0] && x[1])))
+}
+
+pub fn OR(state: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<Option<CVariable>> {
+    let operands = operands(state, ss, fc, 2)?;
+    Ok(Some(truth_table(state, fc, operands, |x| x[0] || x[1])))
+}
+
+pub fn NOT(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    let operands = operands(state, ss, fc, 1)?;
+    Ok(Some(truth_table(state, fc, operands, |x| !x[0])))
+}
+
+/// An operand known at compile time or the variable holding it
+enum Condition {
+    Known(bool),
+    Var(Var),
+}
+
+fn operands(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+    count: usize,
+) -> Result<Vec<Condition>> {
+    if fc.arguments.len() != count {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(count),
+        ));
+    }
+    fc.arguments
+        .iter()
+        .map(|x| {
+            Ok(match x.get_asm_value(ss, state, false)? {
+                AsmValue::Number(a) => Condition::Known(a.0 == 0),
+                AsmValue::Var(a) => Condition::Var(a),
+            })
+        })
+        .collect()
+}
+
+/// Branches on every unknown operand and sets the result in the branches where `holds`.
+/// Nothing is emitted when all the operands are known.
+fn truth_table(
+    state: &mut State,
+    fc: &FunctionCall,
+    operands: Vec<Condition>,
+    holds: impl Fn(&[bool]) -> bool,
+) -> CVariable {
+    if let Some(e) = operands
+        .iter()
+        .map(|x| match x {
+            Condition::Known(a) => Some(*a),
+            Condition::Var(_) => None,
+        })
+        .collect::<Option<Vec<_>>>()
+    {
+        return CVariable::Number(vec![fc.span.clone()], if holds(&e) { 0 } else { 1 });
+    }
+    let out = Var(state.count());
+    state
+        .instructions
+        .push(Mir::Copy(out.clone(), AsmValue::Number(Number(1))));
+    let mut tree = branch(&operands, &mut Vec::new(), &holds, &out);
+    state.instructions.0.append(&mut tree.0);
+    CVariable::Value(vec![fc.span.clone()], out.0)
+}
+
+fn branch(
+    operands: &[Condition],
+    values: &mut Vec<bool>,
+    holds: &impl Fn(&[bool]) -> bool,
+    out: &Var,
+) -> MirCodeBlock {
+    let (first, rest) = match operands.split_first() {
+        Some(e) => e,
+        None if holds(values) => {
+            return MirCodeBlock(vec![Mir::Copy(out.clone(), AsmValue::Number(Number(0)))])
+        }
+        None => return MirCodeBlock(vec![]),
+    };
+    let mut with = |value: bool| {
+        values.push(value);
+        let block = branch(rest, values, holds, out);
+        values.pop();
+        block
+    };
+    match first {
+        Condition::Known(a) => with(*a),
+        Condition::Var(a) => {
+            let (if_true, if_false) = (with(true), with(false));
+            if if_true == if_false {
+                if_true
+            } else {
+                MirCodeBlock(vec![Mir::If0(a.clone(), if_true, if_false)])
+            }
+        }
+    }
+}
@@ -13,17 +13,42 @@ pub fn BREAK(
     ss: &mut ScopedState,
     fc: &FunctionCall,
 ) -> Result<Option<CVariable>> {
-    if !fc.arguments.is_empty() {
-        return Err(CError(
-            vec![fc.span.clone()],
-            CErrorType::WrongNumberOfArgument(0),
-        ));
-    }
+    exit_loop(state, ss, fc, false)
+}
 
-    ss.current_loop
+/// Leaves the loop written around the call, or the one with the given label, and starts it again
+/// if `continues`. A `$` argument or a function leaves the loops of the scope it comes from.
+pub fn exit_loop(
+    state: &mut State,
+    ss: &ScopedState,
+    fc: &FunctionCall,
+    continues: bool,
+) -> Result<Option<CVariable>> {
+    let target = match fc.arguments.as_slice() {
+        [] => ss.loops.last(),
+        [label] => {
+            let (span, label) = label.get_literal()?;
+            let target = ss.loops.iter().rev().find(|x| x.0.as_ref() == Some(label));
+            Some(target.ok_or_else(|| {
+                CError(vec![span.clone()], CErrorType::LoopNotFound(label.clone()))
+            })?)
+        }
+        _ => {
+            return Err(CError(
+                vec![fc.span.clone()],
+                CErrorType::WrongNumberOfArgument(1),
+            ))
+        }
+    };
+    // The loop of the scope may already be compiled when a function defined in it is called
+    let position = target
+        .and_then(|(_, id)| state.loops.iter().rposition(|x| x == id))
         .ok_or_else(|| CError(vec![fc.span.clone()], CErrorType::InvalidBreakOrContinue))?;
-
-    state.instructions.push(Mir::Break);
-
+    let depth = state.loops.len() - 1 - position;
+    state.instructions.push(if continues {
+        Mir::Continue(depth)
+    } else {
+        Mir::Break(depth)
+    });
     Ok(None)
 }
@@ -0,0 +1,139 @@
+//! Comparisons lowered to `If0` decision trees: both operands are decremented together
+//! until one of them reaches 0. The `2^base` steps are unrolled at compile time so a
+//! comparison always runs in constant time.
+//! Like `if0` conditions, the result is 0 when the comparison holds and 1 otherwise.
+
+use crate::compiler::{
+    asm::{AsmValue, Number, Var},
+    error::{CError, CErrorType},
+    mir::{Mir, MirCodeBlock},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+impl Comparison {
+    fn holds(self, a: u8, b: u8) -> bool {
+        match self {
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+            Self::Lt => a < b,
+            Self::Gt => a > b,
+            Self::Le => a <= b,
+            Self::Ge => a >= b,
+        }
+    }
+
+    /// Result once one of the operands reached 0
+    fn outcome(self, a_zero: bool, b_zero: bool) -> bool {
+        match self {
+            Self::Eq => a_zero && b_zero,
+            Self::Ne => !(a_zero && b_zero),
+            Self::Lt => a_zero && !b_zero,
+            Self::Gt => !a_zero && b_zero,
+            Self::Le => a_zero,
+            Self::Ge => b_zero,
+        }
+    }
+}
+
+pub fn EQ(state: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<Option<CVariable>> {
+    compare(state, ss, fc, Comparison::Eq)
+}
+
+pub fn NE(state: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<Option<CVariable>> {
+    compare(state, ss, fc, Comparison::Ne)
+}
+
+pub fn LT(state: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<Option<CVariable>> {
+    compare(state, ss, fc, Comparison::Lt)
+}
+
+pub fn GT(state: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<Option<CVariable>> {
+    compare(state, ss, fc, Comparison::Gt)
+}
+
+pub fn LE(state: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<Option<CVariable>> {
+    compare(state, ss, fc, Comparison::Le)
+}
+
+pub fn GE(state: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<Option<CVariable>> {
+    compare(state, ss, fc, Comparison::Ge)
+}
+
+fn compare(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+    comparison: Comparison,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 2 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(2),
+        ));
+    }
+    let a = fc.arguments[0].get_asm_value(ss, state, false)?;
+    let b = fc.arguments[1].get_asm_value(ss, state, false)?;
+
+    if let (AsmValue::Number(a), AsmValue::Number(b)) = (&a, &b) {
+        return Ok(Some(CVariable::Number(
+            vec![fc.span.clone()],
+            if comparison.holds(a.0, b.0) { 0 } else { 1 },
+        )));
+    }
+
+    // The operands are copied since the tree decrements them
+    let (ta, tb, out) = (Var(state.count()), Var(state.count()), Var(state.count()));
+    state.instructions.push(Mir::Copy(ta.clone(), a));
+    state.instructions.push(Mir::Copy(tb.clone(), b));
+    state
+        .instructions
+        .push(Mir::Copy(out.clone(), AsmValue::Number(Number(1))));
+
+    let set_if = |holds: bool| {
+        MirCodeBlock(if holds {
+            vec![Mir::Copy(out.clone(), AsmValue::Number(Number(0)))]
+        } else {
+            vec![]
+        })
+    };
+    // Built from the innermost level: after `2^base - 1` steps one of the operands is 0
+    let mut tree = MirCodeBlock(vec![]);
+    for _ in 0..2_usize.pow(state.base as u32) {
+        let next = if tree.0.is_empty() {
+            vec![]
+        } else {
+            let mut next = vec![Mir::Decrement(ta.clone()), Mir::Decrement(tb.clone())];
+            next.append(&mut tree.0);
+            next
+        };
+        tree = MirCodeBlock(vec![Mir::If0(
+            ta.clone(),
+            MirCodeBlock(vec![Mir::If0(
+                tb.clone(),
+                set_if(comparison.outcome(true, true)),
+                set_if(comparison.outcome(true, false)),
+            )]),
+            MirCodeBlock(vec![Mir::If0(
+                tb.clone(),
+                set_if(comparison.outcome(false, true)),
+                MirCodeBlock(next),
+            )]),
+        )]);
+    }
+    state.instructions.0.append(&mut tree.0);
+    Ok(Some(CVariable::Value(vec![fc.span.clone()], out.0)))
+}
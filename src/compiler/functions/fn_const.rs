@@ -0,0 +1,69 @@
+use crate::compiler::{
+    error::{CError, CErrorType},
+    mir::{
+        interpreter::{Interpreter, FUEL},
+        MirCodeBlock,
+    },
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// `const(<name>, <expression>)` computes the expression when compiling and binds the name to
+/// the resulting number. The expression can only use numbers, constants and the variables it
+/// creates, it can't read or write registers.
+pub fn CONST(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 2 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(2),
+        ));
+    }
+    let (span, name) = fc.arguments[0].get_literal()?;
+    let expression = &fc.arguments[1];
+    let error = |e: String| {
+        CError(
+            vec![expression.get_span().clone()],
+            CErrorType::NotConstant(e),
+        )
+    };
+
+    // The code of the expression is only run by the interpreter
+    let start = state.last_count();
+    let outer = std::mem::replace(&mut state.instructions, MirCodeBlock(vec![]));
+    let value = expression
+        .get_value(&mut ss.clone(), state, false)
+        .and_then(|x| x.unroll(state));
+    let code = std::mem::replace(&mut state.instructions, outer);
+
+    let number = match value? {
+        Some(CVariable::Number(_, e)) => e,
+        Some(CVariable::Value(_, e)) => {
+            let mut interpreter = Interpreter::new(state.base, FUEL);
+            interpreter.run(&code).map_err(|x| error(x.to_string()))?;
+            if interpreter.memory.keys().any(|x| *x <= start) {
+                return Err(error("it changes variables defined outside".to_owned()));
+            }
+            // The value is a variable the code doesn't write, like `const(y, x)`
+            *interpreter
+                .memory
+                .get(&e)
+                .ok_or_else(|| error("it uses a value only known at runtime".to_owned()))?
+        }
+        Some(_) => return Err(error("it isn't a number".to_owned())),
+        None => {
+            return Err(CError(
+                vec![expression.get_span().clone()],
+                CErrorType::ExpectedVariable,
+            ))
+        }
+    };
+    ss.declare_cvar(name, CVariable::Number(vec![span.clone()], number));
+    Ok(None)
+}
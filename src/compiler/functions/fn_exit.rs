@@ -1,5 +1,5 @@
 use crate::compiler::{
-    asm::Number,
+    asm::{AsmValue, Number},
     error::{CError, CErrorType},
     mir::Mir,
     parser::function_call::FunctionCall,
@@ -9,6 +9,8 @@ use crate::compiler::{
     variable::CVariable,
 };
 
+use super::{fn_assert::NO_ASSERT, fn_print_str::encode};
+
 pub fn EXIT(
     state: &mut State,
     ss: &mut ScopedState,
@@ -22,6 +24,11 @@ pub fn EXIT(
     }
     let k = fc.arguments[0].get_value(ss, state, false)?;
     let tmp = k.to_asm(state)?;
+    for (register, digit) in encode(NO_ASSERT, state.base) {
+        state
+            .instructions
+            .push(Mir::WriteRegister(register, AsmValue::Number(digit)));
+    }
     state.instructions.push(Mir::WriteRegister(Number(0), tmp));
     state.instructions.push(Mir::Stop);
     Ok(None)
@@ -0,0 +1,28 @@
+use crate::compiler::{
+    error::{CError, CErrorType},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// Lists the functions of the current file that can be used by the files importing it.
+pub fn EXPORT(
+    _state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.is_empty() {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(1),
+        ));
+    }
+    let exports = ss.exports.get_or_insert_with(Vec::new);
+    for i in &fc.arguments {
+        let (span, name) = i.get_literal()?;
+        exports.push((span.clone(), name.clone()));
+    }
+    Ok(None)
+}
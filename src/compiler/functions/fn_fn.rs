@@ -1,7 +1,11 @@
+use std::{fmt::Display, rc::Rc};
+
 use crate::compiler::{
+    asm::Var,
     error::{CError, CErrorType, CSpan},
-    parser::{expression::Expression, function_call::FunctionCall},
-    scope::ScopedState,
+    mir::{Mir, MirCodeBlock},
+    parser::{codeblock::CodeBlock, expression::Expression, function_call::FunctionCall},
+    scope::{Overload, ScopedState},
     state::State,
     type_defs::Result,
     variable::CVariable,
@@ -9,7 +13,11 @@ use crate::compiler::{
 
 use super::{set_variable_to_expression, set_variable_to_expression_ref};
 
-enum FnArgument {
+/// How an argument of a function or a field of a struct receives its value:
+/// `&` references a variable, `&*` references or creates it, `$` computes the expression
+/// when used and the others copy the value.
+#[derive(Clone)]
+pub enum FnArgument {
     Reference(String, CSpan),
     Copy(String, CSpan),
     DefineReference(String, CSpan),
@@ -17,7 +25,7 @@ enum FnArgument {
 }
 
 impl FnArgument {
-    fn new(string: &str, span: CSpan) -> Self {
+    pub fn new(string: &str, span: CSpan) -> Self {
         if let Some(string) = string.strip_prefix('&') {
             if let Some(string) = string.strip_prefix('*') {
                 Self::DefineReference(string.to_owned(), span)
@@ -31,7 +39,37 @@ impl FnArgument {
         }
     }
 
-    fn execute(
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Reference(a, _)
+            | Self::Copy(a, _)
+            | Self::DefineReference(a, _)
+            | Self::ExpressionRef(a, _) => a,
+        }
+    }
+
+    pub fn span(&self) -> &CSpan {
+        match self {
+            Self::Reference(_, a)
+            | Self::Copy(_, a)
+            | Self::DefineReference(_, a)
+            | Self::ExpressionRef(_, a) => a,
+        }
+    }
+
+    /// Whether the expression is the kind of argument this mode is meant for: a variable for
+    /// references, a block for `$` and any other value for copies. Used to choose an overload.
+    pub fn prefers(&self, input: &Expression) -> bool {
+        match self {
+            Self::Reference(..) | Self::DefineReference(..) => {
+                matches!(input, Expression::Literal(..))
+            }
+            Self::ExpressionRef(..) => matches!(input, Expression::CodeBlock(..)),
+            Self::Copy(..) => !matches!(input, Expression::Literal(..) | Expression::CodeBlock(..)),
+        }
+    }
+
+    pub fn execute(
         &self,
         input: &Expression,
         function_scope: &mut ScopedState,
@@ -72,12 +110,160 @@ impl FnArgument {
     }
 }
 
+impl Display for FnArgument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reference(a, _) => write!(f, "&{}", a),
+            Self::Copy(a, _) => write!(f, "{}", a),
+            Self::DefineReference(a, _) => write!(f, "&*{}", a),
+            Self::ExpressionRef(a, _) => write!(f, "${}", a),
+        }
+    }
+}
+
+/// A parameter of a function, with the expression used when the call doesn't give it.
+/// A variadic parameter (`...rest`) takes the remaining arguments as a list.
+#[derive(Clone)]
+pub struct Parameter {
+    pub argument: FnArgument,
+    pub default: Option<Expression>,
+    pub variadic: bool,
+}
+
+impl Parameter {
+    /// Reads `name`, `...name` or `name = <default>`
+    fn new(expression: &Expression) -> Result<Self> {
+        let (span, name, default) = match expression {
+            Expression::FunctionCall(_, fc) if fc.name == "=" && fc.arguments.len() == 2 => {
+                let (span, name) = fc.arguments[0].get_literal()?;
+                (span, name, Some(fc.arguments[1].clone()))
+            }
+            e => {
+                let (span, name) = e.get_literal()?;
+                (span, name, None)
+            }
+        };
+        let (name, variadic) = match name.strip_prefix("...") {
+            Some(e) => (e, true),
+            None => (name.as_str(), false),
+        };
+        if variadic && default.is_some() {
+            return Err(CError(vec![span.clone()], CErrorType::InvalidVariadic));
+        }
+        Ok(Self {
+            argument: FnArgument::new(name, span.clone()),
+            default,
+            variadic,
+        })
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.default {
+            Some(e) => write!(f, "{} = {}", self.argument, e.get_span().span.as_str()),
+            None if self.variadic => write!(f, "...{}", self.argument),
+            None => write!(f, "{}", self.argument),
+        }
+    }
+}
+
+/// Gives the expressions of each parameter: the positional arguments come first, the ones
+/// left over go to the variadic parameter, then the named ones. An empty list means the
+/// default value is used, or an empty list for a variadic parameter.
+pub fn bind_arguments<'a>(
+    parameters: &[Parameter],
+    call: &'a FunctionCall,
+) -> Result<Vec<Vec<&'a Expression>>> {
+    let variadic = matches!(parameters.last(), Some(e) if e.variadic);
+    if !variadic && call.arguments.len() > parameters.len() {
+        return Err(CError(
+            vec![call.span.clone()],
+            CErrorType::WrongNumberOfArgument(parameters.len()),
+        ));
+    }
+    let mut out: Vec<Vec<&Expression>> = vec![vec![]; parameters.len()];
+    for (i, argument) in call.arguments.iter().enumerate() {
+        out[i.min(parameters.len() - 1)].push(argument);
+    }
+    for i in &call.named_arguments {
+        let position = parameters
+            .iter()
+            .position(|x| x.argument.name() == i.name)
+            .ok_or_else(|| {
+                CError(
+                    vec![i.span.clone()],
+                    CErrorType::UnknownArgument(i.name.clone()),
+                )
+            })?;
+        if !out[position].is_empty() {
+            return Err(CError(
+                vec![i.span.clone()],
+                CErrorType::ArgumentAlreadyProvided(i.name.clone()),
+            ));
+        }
+        out[position].push(&i.value);
+    }
+    if let Some(e) = parameters
+        .iter()
+        .zip(&out)
+        .find(|(a, b)| a.default.is_none() && !a.variadic && b.is_empty())
+    {
+        return Err(CError(
+            vec![call.span.clone()],
+            CErrorType::MissingArgument(e.0.argument.name().to_owned()),
+        ));
+    }
+    Ok(out)
+}
+
+/// Compiles the body of a function with its arguments bound. It is put in a `Mir::Block` when a
+/// `return` leaves it, the value of the function is then in its return cell.
+fn execute_body(
+    state: &mut State,
+    code: &CodeBlock,
+    mut scope: ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    let cell = state.count();
+    scope.return_to = Some(cell);
+    let outer = std::mem::replace(&mut state.instructions, MirCodeBlock(Vec::new()));
+    state.functions.push((cell, false));
+    let value = code.execute(state, scope);
+    let returns = matches!(state.functions.pop(), Some((_, true)));
+    let value = match value {
+        Ok(e) if returns => e.map(|e| e.to_asm(state)).transpose().map(|e| {
+            if let Some(e) = e {
+                state.instructions.push(Mir::Copy(Var(cell), e));
+            }
+            Some(CVariable::Value(vec![fc.span.clone()], cell))
+        }),
+        e => e,
+    };
+    let body = std::mem::replace(&mut state.instructions, outer);
+    if returns {
+        state.instructions.push(Mir::Block(body));
+    } else {
+        state.instructions.0.extend(body.0);
+    }
+    value
+}
+
 pub fn FN(
     _state: &mut State,
     ss: &mut ScopedState,
     fc: &FunctionCall,
 ) -> Result<Option<CVariable>> {
-    let g = fc.arguments.len();
+    let scos = ss.clone();
+    define_function(ss, fc, scos)
+}
+
+/// Adds the function defined by `fc` to `ss`, its body is executed in a copy of `scos`.
+pub fn define_function(
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+    scos: ScopedState,
+) -> Result<Option<CVariable>> {
     if fc.arguments.len() < 2 {
         return Err(CError(
             vec![fc.span.clone()],
@@ -85,33 +271,77 @@ pub fn FN(
         ));
     }
     let fname = fc.arguments[0].get_literal()?.1;
-    let args: Vec<FnArgument> = fc
-        .arguments
+    let overload = function(ss, &fc.span, &fc.arguments[1..], scos)?;
+    ss.add_overload(fname, overload);
+    Ok(None)
+}
+
+/// Builds a function from its parameters followed by its body, the body is executed in a copy
+/// of `scos`.
+pub fn function(
+    ss: &ScopedState,
+    span: &CSpan,
+    arguments: &[Expression],
+    scos: ScopedState,
+) -> Result<Overload> {
+    let (code, parameters) = arguments
+        .split_last()
+        .ok_or_else(|| CError(vec![span.clone()], CErrorType::WrongNumberOfArgument(1)))?;
+    let args: Vec<Parameter> = parameters
         .iter()
-        .skip(1)
-        .take(g - 2)
-        .map(|x| x.get_literal().map(|(a, b)| FnArgument::new(b, a.clone())))
+        .map(|x| Parameter::new(&x.group_operators(ss)))
         .collect::<Result<_>>()?;
-    let code = fc.arguments[g - 1].get_codeblock()?.1.clone();
-    let scos = ss.clone();
+    if let Some(e) = args.iter().rev().skip(1).find(|x| x.variadic) {
+        return Err(CError(
+            vec![e.argument.span().clone()],
+            CErrorType::InvalidVariadic,
+        ));
+    }
+    let code = code.get_codeblock()?.1.clone();
 
-    ss.add_function(fname, move |a, b, c| {
+    let arguments = args.clone();
+    let handler = move |a: &mut State, b: &mut ScopedState, c: &FunctionCall| {
         let mut scos = scos.clone();
-        let mut vargs = c.arguments.iter();
-        for arg in &args {
-            arg.execute(
-                vargs.next().ok_or_else(|| {
-                    CError(
+        for (arg, input) in args.iter().zip(bind_arguments(&args, c)?) {
+            match (input.as_slice(), &arg.default) {
+                (inputs, _) if arg.variadic => {
+                    // Each element is bound like a single argument then moved in the list
+                    let mut elements = Vec::new();
+                    for i in inputs {
+                        let mut element = ScopedState::default();
+                        arg.argument.execute(i, &mut element, b, a)?;
+                        elements.extend(element.variables.remove(arg.argument.name()));
+                    }
+                    scos.link_variable(
+                        arg.argument.name(),
+                        CVariable::List(vec![arg.argument.span().clone()], elements),
+                    );
+                }
+                ([e], _) => arg.argument.execute(e, &mut scos, b, a)?,
+                // Default values are computed in the scope of the function, after the
+                // previous arguments
+                ([], Some(e)) => {
+                    let mut defaults = scos.clone();
+                    arg.argument.execute(e, &mut scos, &mut defaults, a)?
+                }
+                ([], None) => {
+                    return Err(CError(
+                        vec![c.span.clone()],
+                        CErrorType::MissingArgument(arg.argument.name().to_owned()),
+                    ))
+                }
+                _ => {
+                    return Err(CError(
                         vec![c.span.clone()],
                         CErrorType::WrongNumberOfArgument(args.len()),
-                    )
-                })?,
-                &mut scos,
-                b,
-                a,
-            )?;
+                    ))
+                }
+            }
         }
-        code.execute(a, scos)
-    });
-    Ok(None)
+        execute_body(a, &code, scos, c)
+    };
+    Ok(Overload {
+        handler: Rc::new(Box::new(handler)),
+        arguments: Some(arguments),
+    })
 }
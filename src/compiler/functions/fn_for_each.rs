@@ -0,0 +1,45 @@
+use crate::compiler::{
+    error::{CError, CErrorType},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// `for_each(<name>, <list or array>, <code block>)` expands the block once per element, with
+/// the element bound to the name. The loop is unrolled at compile time so `break` and
+/// `continue` refer to the enclosing loop.
+pub fn FOR_EACH(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 3 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(3),
+        ));
+    }
+    let (span, name) = fc.arguments[0].get_literal()?;
+    let elements = match fc.arguments[1].get_value(ss, state, false)?.unroll(state)? {
+        Some(CVariable::List(_, e)) => e,
+        Some(CVariable::Array(a, e)) => e
+            .into_iter()
+            .map(|x| CVariable::Value(a.clone(), x))
+            .collect(),
+        _ => {
+            return Err(CError(
+                vec![fc.arguments[1].get_span().clone()],
+                CErrorType::ExpectedList,
+            ))
+        }
+    };
+    let code = fc.arguments[2].get_codeblock()?.1;
+    for element in elements {
+        let mut scope = ss.clone();
+        scope.link_variable(name, element.chain(span.clone()));
+        code.execute(state, scope)?;
+    }
+    Ok(None)
+}
@@ -53,7 +53,7 @@ pub fn IF0(
             _ => None,
         };
 
-        tmp_state.push(Mir::If0(k1, if_1, if_2));
+        tmp_state.push(Mir::If0(k1, if_2, if_1));
         state.instructions = tmp_state;
 
         Ok(outvar.map(|x| CVariable::Value(vec![fc.span.clone()], x.0)))
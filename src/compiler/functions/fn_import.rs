@@ -0,0 +1,110 @@
+use std::{path::Path, rc::Rc};
+
+use crate::compiler::{
+    error::{CError, CErrorType},
+    parser::function_call::FunctionCall,
+    resolver::{canonicalize, not_found, resolve_file},
+    scope::ScopedState,
+    state::{Module, State},
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// `import(<file>, as: <name>)` runs the file in its own scope, only once per program,
+/// and makes its exported functions available as `name.function`.
+pub fn IMPORT(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 1 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(1),
+        ));
+    }
+    if let Some(e) = fc.named_arguments.iter().find(|x| x.name != "as") {
+        return Err(CError(
+            vec![e.span.clone()],
+            CErrorType::UnknownArgument(e.name.clone()),
+        ));
+    }
+    let (span, fname) = fc.arguments[0].get_literal()?;
+    let path = resolve_file(state, &fc.span, fname)
+        .ok_or_else(|| CError(vec![span.clone()], not_found(state, fname)))?;
+    let alias = match fc.get_named_argument("as") {
+        Some(e) => e.value.get_literal()?.1.clone(),
+        None => path
+            .file_stem()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_else(|| fname.clone()),
+    };
+    let canonical = canonicalize(&path);
+
+    if let Some(e) = state.importing.iter().position(|x| x == &canonical) {
+        let mut chain: Vec<String> = state.importing[e..]
+            .iter()
+            .map(|x| display_path(x))
+            .collect();
+        chain.push(display_path(&canonical));
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::CircularImport(chain),
+        ));
+    }
+
+    let module = match state.modules.get(&canonical) {
+        Some(e) => e.clone(),
+        None => {
+            state.importing.push(canonical.clone());
+            let mut scope = ScopedState::new();
+            let result = crate::execute_file(
+                &path.to_string_lossy(),
+                state,
+                &mut scope,
+                vec![span.clone()],
+            );
+            state.importing.pop();
+            result?;
+            let module = exported_functions(&scope)?;
+            state.modules.insert(canonical, module.clone());
+            module
+        }
+    };
+    for (name, handler) in module.iter() {
+        ss.add_overloads(&format!("{}.{}", alias, name), handler.clone());
+    }
+    Ok(None)
+}
+
+/// The functions listed by `export`, or every function defined in the file if there is
+/// no `export` call. Qualified functions coming from imports are never re-exported.
+fn exported_functions(scope: &ScopedState) -> Result<Module> {
+    let module = match &scope.exports {
+        Some(e) => e
+            .iter()
+            .map(|(span, name)| match scope.get_function(name) {
+                Some(e) => Ok((name.clone(), e.clone())),
+                None => Err(CError(
+                    vec![span.clone()],
+                    CErrorType::FunctionNotFound(name.clone()),
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => {
+            let builtins = ScopedState::new();
+            scope
+                .functions()
+                .filter(|(x, _)| builtins.get_function(x).is_none() && !x.contains('.'))
+                .map(|(x, y)| (x.clone(), y.clone()))
+                .collect()
+        }
+    };
+    Ok(Rc::new(module))
+}
+
+fn display_path(path: &Path) -> String {
+    path.file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -1,13 +1,13 @@
-use std::path::Path;
-
 use crate::compiler::{
     error::{CError, CErrorType},
     parser::function_call::FunctionCall,
+    resolver::{not_found, resolve_file},
     scope::ScopedState,
     state::State,
     type_defs::Result,
     variable::CVariable,
 };
+
 pub fn INCLUDE(
     state: &mut State,
     ss: &mut ScopedState,
@@ -19,15 +19,9 @@ pub fn INCLUDE(
             CErrorType::WrongNumberOfArgument(1),
         ));
     }
-    let k = fc.span.get_filename();
     let (span, fname) = fc.arguments[0].get_literal()?;
-    let mut path = Path::new(k).to_path_buf();
-    path.pop();
-    crate::execute_file(
-        path.join(fname).to_str().unwrap(),
-        state,
-        ss,
-        vec![span.clone()],
-    )?;
+    let path = resolve_file(state, &fc.span, fname)
+        .ok_or_else(|| CError(vec![span.clone()], not_found(state, fname)))?;
+    crate::execute_file(&path.to_string_lossy(), state, ss, vec![span.clone()])?;
     Ok(None)
 }
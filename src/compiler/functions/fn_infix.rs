@@ -0,0 +1,70 @@
+use crate::compiler::{
+    error::{CError, CErrorType},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+#[derive(Clone, Copy)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+/// How a binary operator binds, operators that aren't declared have the lowest precedence and
+/// are right associative.
+#[derive(Clone, Copy)]
+pub struct Operator {
+    pub precedence: u8,
+    pub associativity: Associativity,
+}
+
+impl Default for Operator {
+    fn default() -> Self {
+        Self {
+            precedence: 0,
+            associativity: Associativity::Right,
+        }
+    }
+}
+
+impl Operator {
+    pub fn new(precedence: u8, associativity: Associativity) -> Self {
+        Self {
+            precedence,
+            associativity,
+        }
+    }
+}
+
+/// `infix(<operator>, <precedence>, <left|right>)` sets how the operator binds in the current
+/// scope, higher precedences are applied first.
+pub fn INFIX(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 3 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(3),
+        ));
+    }
+    let (_, name) = fc.arguments[0].get_literal()?;
+    let precedence = fc.arguments[1].as_number(ss, state, false)?.0;
+    let associativity = match fc.arguments[2].get_literal()? {
+        (_, e) if e == "left" => Associativity::Left,
+        (_, e) if e == "right" => Associativity::Right,
+        (span, e) => {
+            return Err(CError(
+                vec![span.clone()],
+                CErrorType::InvalidAssociativity(e.clone()),
+            ))
+        }
+    };
+    ss.operators
+        .insert(name.clone(), Operator::new(precedence, associativity));
+    Ok(None)
+}
@@ -0,0 +1,20 @@
+use crate::compiler::{
+    parser::function_call::FunctionCall, scope::ScopedState, state::State, type_defs::Result,
+    variable::CVariable,
+};
+
+use super::fn_fn::function;
+
+/// `lambda(<arguments...>, <code block>)` returns a function without a name, it can be stored
+/// in a variable or passed as an argument and called like the functions defined with `fn`.
+pub fn LAMBDA(
+    _state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    let overload = function(ss, &fc.span, &fc.arguments, ss.clone())?;
+    Ok(Some(CVariable::Function(
+        vec![fc.span.clone()],
+        vec![overload],
+    )))
+}
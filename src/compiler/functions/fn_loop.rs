@@ -13,21 +13,30 @@ pub fn LOOP(
     ss: &mut ScopedState,
     fc: &FunctionCall,
 ) -> Result<Option<CVariable>> {
-    if fc.arguments.len() != 1 {
-        return Err(CError(
-            vec![fc.span.clone()],
-            CErrorType::WrongNumberOfArgument(1),
-        ));
-    }
+    let (label, body) = match fc.arguments.as_slice() {
+        [body] => (None, body),
+        [label, body] => (Some(label.get_literal()?.1.clone()), body),
+        _ => {
+            return Err(CError(
+                vec![fc.span.clone()],
+                CErrorType::WrongNumberOfArgument(1),
+            ))
+        }
+    };
 
     let mut k = ss.clone();
 
     let mut tmp_state = state.instructions.clone();
     state.instructions = MirCodeBlock(vec![]);
 
-    k.current_loop = Some(());
-
-    fc.arguments[0].get_codeblock()?.1.execute(state, k)?;
+    let id = state.count();
+    k.loops.push((label, id));
+    state.loops.push(id);
+    let result = body
+        .get_codeblock()
+        .and_then(|(_, code)| code.execute(state, k));
+    state.loops.pop();
+    result?;
 
     tmp_state.push(Mir::Loop(state.instructions.clone()));
     state.instructions = tmp_state;
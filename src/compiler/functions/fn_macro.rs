@@ -0,0 +1,19 @@
+use crate::compiler::{
+    parser::function_call::FunctionCall, scope::ScopedState, state::State, type_defs::Result,
+    variable::CVariable,
+};
+
+use super::fn_fn::define_function;
+
+/// `macro(<name>, <arguments...>, <code block>)` defines a hygienic function: its body only sees
+/// its arguments and the functions of the scope, so every other variable it uses is fresh for
+/// each call and can't collide with the variables of the caller or of the `$` arguments.
+pub fn MACRO(
+    _state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    let mut scos = ss.clone();
+    scos.variables.clear();
+    define_function(ss, fc, scos)
+}
@@ -0,0 +1,95 @@
+use crate::compiler::{
+    asm::{AsmValue, Var},
+    error::{CError, CErrorType},
+    mir::{Mir, MirCodeBlock},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// `match(<value>, <number>: <block>..., _: <block>)` executes the case of the value, or the
+/// `_` case if there is no case for it. The cases are reached with a single indexed jump.
+pub fn MATCH(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 1 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(1),
+        ));
+    }
+    let mut table = vec![None; 2_usize.pow(state.base as u32)];
+    let mut default = None;
+    for (i, case) in fc.named_arguments.iter().enumerate() {
+        let entry = if case.name == "_" {
+            &mut default
+        } else {
+            case.name
+                .parse::<usize>()
+                .ok()
+                .and_then(|x| table.get_mut(x))
+                .ok_or_else(|| CError(vec![case.span.clone()], CErrorType::InvalidNumber))?
+        };
+        if entry.is_some() {
+            return Err(CError(
+                vec![case.span.clone()],
+                CErrorType::DuplicateCase(case.name.clone()),
+            ));
+        }
+        *entry = Some(i);
+    }
+
+    let value = fc.arguments[0].get_asm_value(ss, state, false)?;
+    let var = match value {
+        AsmValue::Var(e) => e,
+        // The case is known at compile time
+        AsmValue::Number(e) => {
+            return match table.get(e.0 as usize).copied().flatten().or(default) {
+                Some(i) => fc.named_arguments[i].value.execute(&mut ss.clone(), state),
+                None => Ok(None),
+            }
+        }
+    };
+
+    let tmp_state = std::mem::replace(&mut state.instructions, MirCodeBlock(vec![]));
+    let mut cases = Vec::new();
+    let mut results = Vec::new();
+    for case in &fc.named_arguments {
+        let result = match case.value.execute(&mut ss.clone(), state)? {
+            Some(e) => Some(e.to_asm(state)?),
+            None => None,
+        };
+        cases.push(std::mem::replace(
+            &mut state.instructions,
+            MirCodeBlock(vec![]),
+        ));
+        results.push(result);
+    }
+    let default = default.unwrap_or_else(|| {
+        cases.push(MirCodeBlock(vec![]));
+        results.push(None);
+        cases.len() - 1
+    });
+    let table: Vec<usize> = table.into_iter().map(|x| x.unwrap_or(default)).collect();
+
+    // The match has a value if every reachable case has one
+    let outvar = if table.iter().all(|x| results[*x].is_some()) {
+        let out = Var(state.count());
+        for (i, result) in results.into_iter().enumerate() {
+            if let Some(e) = result {
+                cases[i].push(Mir::Copy(out.clone(), e));
+            }
+        }
+        Some(out)
+    } else {
+        None
+    };
+
+    state.instructions = tmp_state;
+    state.instructions.push(Mir::Switch(var, table, cases));
+    Ok(outvar.map(|x| CVariable::Value(vec![fc.span.clone()], x.0)))
+}
@@ -0,0 +1,66 @@
+use crate::compiler::{
+    asm::{AsmValue, Number},
+    error::{CError, CErrorType},
+    mir::Mir,
+    parser::{expression::Expression, function_call::FunctionCall},
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// `print_str(<string or character>...)` prints the text, the characters are known at compile
+/// time so they are written directly to the registers.
+pub fn PRINT_STR(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.is_empty() {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(1),
+        ));
+    }
+    for i in &fc.arguments {
+        let text = match i {
+            Expression::String(_, e) => e.as_bytes().to_vec(),
+            Expression::Character(_, e) => vec![*e],
+            e => vec![e.as_number(ss, state, false)?.0],
+        };
+        for c in text {
+            for (register, digit) in encode(c, state.base) {
+                state
+                    .instructions
+                    .push(Mir::WriteRegister(register, AsmValue::Number(digit)));
+            }
+            state
+                .instructions
+                .push(Mir::WriteRegister(Number(0), AsmValue::Number(Number(1))));
+        }
+    }
+    Ok(None)
+}
+
+/// Splits a character in the register digits read by `InterruptedCythan::set_value`: the
+/// `8 / base` full digits go in the registers `1..`, most significant first, and the remaining
+/// `8 % base` bits in the register `8 / base + 2`.
+pub fn encode(c: u8, base: u8) -> Vec<(Number, Number)> {
+    let rest = 8 % base as u32;
+    let mut high = c as u32 >> rest;
+    let mut out: Vec<(Number, Number)> = (1..=8 / base)
+        .rev()
+        .map(|x| {
+            let digit = high % 2_u32.pow(base as u32);
+            high /= 2_u32.pow(base as u32);
+            (Number(x), Number(digit as u8))
+        })
+        .collect();
+    if rest != 0 {
+        out.push((
+            Number(8 / base + 2),
+            Number((c as u32 % 2_u32.pow(rest)) as u8),
+        ));
+    }
+    out
+}
@@ -0,0 +1,145 @@
+use crate::compiler::{
+    asm::{AsmValue, Number, Var},
+    error::{CError, CErrorType},
+    mir::{Mir, MirCodeBlock},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// `read_char()` reads a character of the input, 0 once the input is over. It is a single cell
+/// when a character fits in one, otherwise an array of its digits, the most significant first.
+pub fn READ_CHAR(
+    state: &mut State,
+    _ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if !fc.arguments.is_empty() {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(0),
+        ));
+    }
+    let cells = read(state);
+    Ok(Some(match cells.as_slice() {
+        [cell] => CVariable::Value(vec![fc.span.clone()], *cell),
+        _ => CVariable::Array(vec![fc.span.clone()], cells),
+    }))
+}
+
+/// `read_digit()` reads a character of the input and returns its digit, or the greatest value of
+/// a cell when it isn't a digit or the input is over.
+pub fn READ_DIGIT(
+    state: &mut State,
+    _ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if !fc.arguments.is_empty() {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(0),
+        ));
+    }
+    if state.base < 4 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::DigitDoesntFit(state.base),
+        ));
+    }
+    let digits = read(state);
+    let out = Var(state.count());
+    // Only the low bits of the character fit in the registers when the base doesn't divide 8
+    let bits = digits.len() as u32 * state.base as u32;
+    let zero = b'0' as u32 % (1 << bits);
+    let tree = Tree {
+        base: state.base as u32,
+        digits: zero..zero + 10,
+        out: &out,
+        not_digit: ((1_u32 << state.base) - 1) as u8,
+    };
+    state.instructions.push(tree.switch(&digits, 0));
+    Ok(Some(CVariable::Value(vec![fc.span.clone()], out.0)))
+}
+
+/// `eof()` is 0 if the last read reached the end of the input and 1 if it read a character, so it
+/// tells the end apart from a `'\0'` or from a character which isn't a digit. `InterruptedCythan`
+/// writes it in the register after the ones of the character, it is 0 before the first read.
+pub fn EOF(
+    state: &mut State,
+    _ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if !fc.arguments.is_empty() {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(0),
+        ));
+    }
+    let cell = state.count();
+    state
+        .instructions
+        .push(Mir::ReadRegister(Var(cell), Number(8 / state.base + 1)));
+    Ok(Some(CVariable::Value(vec![fc.span.clone()], cell)))
+}
+
+/// Asks `InterruptedCythan` for a character and copies the registers it is written in
+fn read(state: &mut State) -> Vec<usize> {
+    state
+        .instructions
+        .push(Mir::WriteRegister(Number(0), AsmValue::Number(Number(2))));
+    (1..=8 / state.base)
+        .map(|register| {
+            let cell = state.count();
+            state
+                .instructions
+                .push(Mir::ReadRegister(Var(cell), Number(register)));
+            cell
+        })
+        .collect()
+}
+
+/// Switches on the digits of a character, most significant first, to set `out` to its digit
+struct Tree<'a> {
+    base: u32,
+    /// The characters of the digits, as read from the registers
+    digits: std::ops::Range<u32>,
+    out: &'a Var,
+    not_digit: u8,
+}
+
+impl Tree<'_> {
+    /// `prefix` is the value of the digits already switched on
+    fn switch(&self, cells: &[usize], prefix: u32) -> Mir {
+        let (cell, rest) = match cells.split_first() {
+            Some(e) => e,
+            None => {
+                let digit = if self.digits.contains(&prefix) {
+                    (prefix - self.digits.start) as u8
+                } else {
+                    self.not_digit
+                };
+                return Mir::Copy(self.out.clone(), AsmValue::Number(Number(digit)));
+            }
+        };
+        // The values which can't start a digit share the first case
+        let mut cases = vec![MirCodeBlock(vec![Mir::Copy(
+            self.out.clone(),
+            AsmValue::Number(Number(self.not_digit)),
+        )])];
+        let size = 1 << self.base;
+        let span = 1 << (self.base * rest.len() as u32);
+        let mut table = Vec::new();
+        for value in 0..size {
+            let start = (prefix * size + value) * span;
+            if start + span <= self.digits.start || start >= self.digits.end {
+                table.push(0);
+            } else {
+                table.push(cases.len());
+                cases.push(MirCodeBlock(vec![self.switch(rest, prefix * size + value)]));
+            }
+        }
+        Mir::Switch(Var(*cell), table, cases)
+    }
+}
@@ -0,0 +1,38 @@
+use crate::compiler::{
+    asm::Var,
+    error::{CError, CErrorType},
+    mir::Mir,
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// Leaves the function the call is written in, its value becomes the one of the function
+pub fn RETURN(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 1 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(1),
+        ));
+    }
+    let value = fc.arguments[0].get_value(ss, state, false)?.to_asm(state)?;
+    // Like `break`, a function defined in another one can be called after the outer one is compiled
+    let (cell, position) = ss
+        .return_to
+        .and_then(|cell| Some((cell, state.functions.iter().rposition(|x| x.0 == cell)?)))
+        .ok_or_else(|| CError(vec![fc.span.clone()], CErrorType::InvalidReturn))?;
+    // Every function between the call and the one to leave has to be put in a block
+    for function in &mut state.functions[position..] {
+        function.1 = true;
+    }
+    let depth = state.functions.len() - 1 - position;
+    state.instructions.push(Mir::Copy(Var(cell), value));
+    state.instructions.push(Mir::Exit(depth));
+    Ok(None)
+}
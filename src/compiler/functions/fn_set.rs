@@ -7,13 +7,17 @@ use crate::compiler::{
     variable::CVariable,
 };
 
-use super::set_variable;
+use super::{fn_array::set_element, set_variable};
 
 pub fn SET(
     state: &mut State,
     ss: &mut ScopedState,
     fc: &FunctionCall,
 ) -> Result<Option<CVariable>> {
+    if fc.arguments.len() == 3 {
+        set_element(state, ss, fc)?;
+        return Ok(None);
+    }
     if fc.arguments.len() != 2 {
         return Err(CError(
             vec![fc.span.clone()],
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use crate::compiler::{
     error::{CError, CErrorType},
+    functions::fn_fn::FnArgument,
     parser::function_call::FunctionCall,
     scope::ScopedState,
     state::State,
@@ -23,7 +24,7 @@ pub fn STRUCT(_: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<
         .get_literal()
         .map(|(a, b)| (a.clone(), b.to_owned()))?;
 
-    let fields: Vec<String> = args
+    let fields: Vec<FnArgument> = args
         .map(|x| {
             let (span, string) = x.get_literal()?;
             Ok(if let Some(pos) = string.find("..") {
@@ -35,19 +36,20 @@ pub fn STRUCT(_: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<
                     let second: u8 = string[(pos + 2)..]
                         .parse()
                         .map_err(|_| CError(vec![span.clone()], CErrorType::ExpectedNumber))?;
-                    (first..second).map(|x| x.to_string()).collect()
+                    (first..second)
+                        .map(|x| FnArgument::new(&x.to_string(), span.clone()))
+                        .collect()
                 } else {
-                    vec![string.to_owned()]
+                    vec![FnArgument::new(string, span.clone())]
                 }
             } else {
-                vec![string.to_owned()]
+                vec![FnArgument::new(string, span.clone())]
             })
         })
         .collect::<Result<Vec<_>>>()?
         .into_iter()
         .flatten()
         .collect();
-    // TODO: Add ExpressionRef, Ref fields in Structs
     ss.add_function(&var.clone(), move |a, b, c| {
         if c.arguments.len() != fields.len() {
             return Err(CError(
@@ -56,16 +58,26 @@ pub fn STRUCT(_: &mut State, ss: &mut ScopedState, fc: &FunctionCall) -> Result<
             ));
         }
 
+        // The fields are bound like function arguments, in a scope of their own
+        let mut scope = ScopedState::default();
+        for (x, y) in c.arguments.iter().zip(fields.iter()) {
+            y.execute(x, &mut scope, b, a)?;
+        }
         Ok(Some(CVariable::Struct(
             vec![c.span.clone()],
             StructRef {
                 span: vec![s.clone()],
                 name: var.clone(),
-                fields: c
-                    .arguments
+                fields: fields
                     .iter()
-                    .zip(fields.iter())
-                    .map(|(x, y)| Ok((y.clone(), x.get_value(b, a, false)?)))
+                    .map(|x| {
+                        Ok((
+                            x.name().to_owned(),
+                            scope
+                                .get_variable(std::slice::from_ref(&c.span), x.name())?
+                                .clone(),
+                        ))
+                    })
                     .collect::<Result<HashMap<String, CVariable>>>()?,
             },
         )))
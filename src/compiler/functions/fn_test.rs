@@ -0,0 +1,36 @@
+use crate::compiler::{
+    error::{CError, CErrorType},
+    parser::function_call::FunctionCall,
+    scope::ScopedState,
+    state::State,
+    type_defs::Result,
+    variable::CVariable,
+};
+
+/// `test(<name>, <code block>)` declares a test, the block is only compiled by `cyc test` in a
+/// program of its own.
+pub fn TEST(
+    state: &mut State,
+    ss: &mut ScopedState,
+    fc: &FunctionCall,
+) -> Result<Option<CVariable>> {
+    if fc.arguments.len() != 2 {
+        return Err(CError(
+            vec![fc.span.clone()],
+            CErrorType::WrongNumberOfArgument(2),
+        ));
+    }
+    let (span, name) = fc.arguments[0].get_literal()?;
+    let code = fc.arguments[1].get_codeblock()?.1;
+    if state.tests.iter().any(|x| &x.1 == name) {
+        return Err(CError(
+            vec![span.clone()],
+            CErrorType::DuplicateTest(name.clone()),
+        ));
+    }
+    state.tests.push((span.clone(), name.clone()));
+    if state.test.as_ref() == Some(name) {
+        code.execute(state, ss.clone())?;
+    }
+    Ok(None)
+}
@@ -1,20 +1,37 @@
 #![allow(non_snake_case)]
 
+pub mod fn_array;
+pub mod fn_assert;
+pub mod fn_bool;
 pub mod fn_break;
+pub mod fn_cmp;
+pub mod fn_const;
 pub mod fn_continue;
 pub mod fn_dec;
 pub mod fn_exit;
+pub mod fn_export;
 pub mod fn_fn;
+pub mod fn_for_each;
 pub mod fn_get_field;
 pub mod fn_get_reg;
 pub mod fn_if0;
+pub mod fn_import;
 pub mod fn_inc;
 pub mod fn_include;
+pub mod fn_infix;
+pub mod fn_lambda;
 pub mod fn_let;
 pub mod fn_loop;
+pub mod fn_macro;
+pub mod fn_match;
+pub mod fn_print_str;
+pub mod fn_read;
+pub mod fn_return;
 pub mod fn_set;
+pub mod fn_set_field;
 pub mod fn_set_reg;
 pub mod fn_struct;
+pub mod fn_test;
 
 use crate::compiler::{
     asm::Var,
@@ -36,13 +53,26 @@ pub fn set_variable_to_expression(
     span: CSpan,
     declare: bool,
 ) -> Result<()> {
+    let k2 = fc
+        .get_value(ss1, state, false)?
+        .unroll(state)?
+        .ok_or_else(|| CError(vec![span.clone()], CErrorType::ExpectedVariable))?;
+    // Structs and arrays are copied in fresh cells
+    if let CVariable::Struct(..)
+    | CVariable::Array(..)
+    | CVariable::List(..)
+    | CVariable::Function(..) = &k2
+    {
+        let copy = k2.deep_copy(state)?;
+        ss.declare_cvar(var, copy);
+        return Ok(());
+    }
     let k1: Var = if declare {
         ss.declare_variable(var, span, state).into()
     } else {
         ss.get_or_declare_variable(var, &span, state)
             .as_var(state)?
     }; // Changed to replace var
-    let k2 = fc.get_value(ss1, state, false)?;
     let tmp = k2.to_asm(state)?;
     state.instructions.push(Mir::Copy(k1, tmp));
     Ok(())
@@ -70,23 +100,46 @@ pub fn set_variable(
     span: CSpan,
     declare: bool,
 ) -> Result<()> {
-    let k3 = span.clone();
+    // `set(a.b, value)` updates the field in place
+    if let Some(field) = ss.get_field_path(&span, var, state)? {
+        let value = fc
+            .get_value(ss, state, false)?
+            .unroll(state)?
+            .ok_or_else(|| CError(vec![span.clone()], CErrorType::ExpectedVariable))?;
+        return field.assign(&value, state);
+    }
+    let k2 = fc
+        .get_value(ss, state, false)?
+        .unroll(state)?
+        .ok_or_else(|| CError(vec![span.clone()], CErrorType::ExpectedVariable))?;
+    // Structs and arrays are copied in the cells of the variable if it already holds one, so the
+    // references to it see the change. Otherwise the value of another variable is copied in fresh
+    // cells, a value computed by a call is already a fresh one.
+    if let CVariable::Struct(..)
+    | CVariable::Array(..)
+    | CVariable::List(..)
+    | CVariable::Function(..) = &k2
+    {
+        match ss.variables.get(var) {
+            Some(e @ (CVariable::Struct(..) | CVariable::Array(..) | CVariable::List(..)))
+                if !declare =>
+            {
+                e.clone().assign(&k2, state)?;
+            }
+            _ if matches!(fc, Expression::Literal(..)) => {
+                let copy = k2.deep_copy(state)?;
+                ss.declare_cvar(var, copy);
+            }
+            _ => ss.declare_cvar(var, k2),
+        }
+        return Ok(());
+    }
     let k1: Var = if declare {
         ss.declare_variable(var, span, state).into()
     } else {
         ss.get_or_declare_variable(var, &span, state)
             .as_var(state)?
     }; // Changed to replace var
-    let k2 = fc
-        .get_value(ss, state, false)?
-        .unroll(state)?
-        .ok_or_else(|| CError(vec![k3], CErrorType::ExpectedVariable))?;
-    if let CVariable::Struct(a, b) = &k2 {
-        ss.variables
-            .insert(var.to_string(), CVariable::Struct(a.clone(), b.clone()));
-
-        return Ok(());
-    }
     let tmp = k2.to_asm(state)?;
     state.instructions.push(Mir::Copy(k1, tmp));
     Ok(())
@@ -0,0 +1,225 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::compiler::asm::{AsmValue, Number};
+
+use super::{Mir, MirCodeBlock};
+
+/// The number of instructions the interpreter runs before giving up, so compiling never hangs on
+/// a loop that doesn't end.
+pub const FUEL: usize = 100_000;
+
+/// Why a block can't be evaluated when compiling
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    /// The variable is read before the block gives it a value
+    UnknownVariable(usize),
+    /// The block reads or writes registers without `Io`
+    Impure,
+    /// The block stops the program
+    Stopped,
+    /// The block breaks or continues a loop around it, or returns from a function around it
+    LeavesBlock,
+    OutOfFuel,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownVariable(_) => write!(f, "it uses a value only known at runtime"),
+            Self::Impure => write!(f, "it reads or writes registers"),
+            Self::Stopped => write!(f, "it stops the program"),
+            Self::LeavesBlock => write!(f, "it leaves a loop or a function around it"),
+            Self::OutOfFuel => write!(f, "it doesn't finish in {} steps", FUEL),
+        }
+    }
+}
+
+/// How a block ended, with the number of loops or blocks to leave before the one to break,
+/// continue or exit
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Flow {
+    Normal,
+    Break(usize),
+    Continue(usize),
+    Exit(usize),
+}
+
+/// The registers of the machine, writing 1 in the register 0 prints the character in the next
+/// registers and writing 2 reads one in them, like `InterruptedCythan`.
+#[derive(Default)]
+pub struct Io {
+    pub registers: HashMap<u8, u8>,
+    pub output: Vec<u8>,
+    input: std::vec::IntoIter<u8>,
+}
+
+impl Io {
+    pub fn new(input: Vec<u8>) -> Self {
+        Self {
+            input: input.into_iter(),
+            ..Self::default()
+        }
+    }
+
+    /// The value of the register 0, the exit code once the program stopped
+    pub fn exit_code(&self) -> u8 {
+        self.register(0)
+    }
+
+    fn register(&self, register: u8) -> u8 {
+        self.registers.get(&register).copied().unwrap_or(0)
+    }
+
+    fn write(&mut self, register: u8, value: u8, base: u8) {
+        self.registers.insert(register, value);
+        if register != 0 {
+            return;
+        }
+        let digits = 8 / base;
+        let digit = 1_u32 << base;
+        let rest = 1_u32 << (8 % base);
+        if value == 1 {
+            let high = (1..=digits).fold(0, |x, n| x * digit + self.register(n) as u32 % digit);
+            let low = self.register(digits + 2) as u32 % rest;
+            self.output.push((high * rest + low) as u8);
+        } else if value == 2 {
+            // Reading past the end of the input gives 0, the register after the character tells
+            // whether one was read
+            let input = self.input.next();
+            let mut c = input.unwrap_or(0) as u32;
+            for n in (1..=digits).rev() {
+                self.registers.insert(n, (c % digit) as u8);
+                c /= digit;
+            }
+            self.registers.insert(digits + 1, input.is_some() as u8);
+        }
+    }
+}
+
+/// Runs `Mir` on known values, the variables wrap like in the compiled program (`2^base`).
+/// With `Io` it runs a whole program: the registers can be used and the variables start at 0.
+pub struct Interpreter {
+    pub memory: HashMap<usize, u8>,
+    pub io: Option<Io>,
+    base: u8,
+    modulo: u16,
+    fuel: usize,
+}
+
+impl Interpreter {
+    pub fn new(base: u8, fuel: usize) -> Self {
+        Self {
+            memory: HashMap::new(),
+            io: None,
+            base,
+            modulo: 1 << base,
+            fuel,
+        }
+    }
+
+    pub fn with_io(base: u8, fuel: usize, io: Io) -> Self {
+        Self {
+            io: Some(io),
+            ..Self::new(base, fuel)
+        }
+    }
+
+    /// Runs a whole block, which can't leave the loops around it
+    pub fn run(&mut self, block: &MirCodeBlock) -> Result<(), EvalError> {
+        match self.block(block)? {
+            Flow::Normal => Ok(()),
+            _ => Err(EvalError::LeavesBlock),
+        }
+    }
+
+    fn block(&mut self, block: &MirCodeBlock) -> Result<Flow, EvalError> {
+        for i in &block.0 {
+            match self.step(i)? {
+                Flow::Normal => (),
+                e => return Ok(e),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn step(&mut self, mir: &Mir) -> Result<Flow, EvalError> {
+        self.fuel = self.fuel.checked_sub(1).ok_or(EvalError::OutOfFuel)?;
+        match mir {
+            Mir::Copy(a, b) => {
+                let value = self.value(b)?;
+                self.memory.insert(a.0, value);
+            }
+            Mir::Increment(a) => {
+                let value = (self.read(a.0)? as u16 + 1) % self.modulo;
+                self.memory.insert(a.0, value as u8);
+            }
+            Mir::Decrement(a) => {
+                let value = (self.read(a.0)? as u16 + self.modulo - 1) % self.modulo;
+                self.memory.insert(a.0, value as u8);
+            }
+            Mir::If0(a, b, c) => {
+                return if self.read(a.0)? == 0 {
+                    self.block(b)
+                } else {
+                    self.block(c)
+                }
+            }
+            Mir::Switch(a, b, c) => {
+                let value = self.read(a.0)? as usize;
+                return match b.get(value) {
+                    Some(e) => self.block(&c[*e]),
+                    None => Err(EvalError::Stopped),
+                };
+            }
+            Mir::Loop(a) => loop {
+                self.fuel = self.fuel.checked_sub(1).ok_or(EvalError::OutOfFuel)?;
+                match self.block(a)? {
+                    Flow::Normal | Flow::Continue(0) => (),
+                    Flow::Break(0) => break,
+                    Flow::Break(e) => return Ok(Flow::Break(e - 1)),
+                    Flow::Continue(e) => return Ok(Flow::Continue(e - 1)),
+                    e => return Ok(e),
+                }
+            },
+            Mir::Block(a) => {
+                return Ok(match self.block(a)? {
+                    Flow::Exit(0) => Flow::Normal,
+                    Flow::Exit(e) => Flow::Exit(e - 1),
+                    e => e,
+                })
+            }
+            Mir::Exit(a) => return Ok(Flow::Exit(*a)),
+            Mir::Break(a) => return Ok(Flow::Break(*a)),
+            Mir::Continue(a) => return Ok(Flow::Continue(*a)),
+            Mir::Stop => return Err(EvalError::Stopped),
+            Mir::ReadRegister(a, Number(b)) => {
+                let value = self.io.as_ref().ok_or(EvalError::Impure)?.register(*b);
+                self.memory.insert(a.0, (value as u16 % self.modulo) as u8);
+            }
+            Mir::WriteRegister(Number(a), b) => {
+                let value = self.value(b)?;
+                let base = self.base;
+                self.io
+                    .as_mut()
+                    .ok_or(EvalError::Impure)?
+                    .write(*a, value, base);
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn read(&self, var: usize) -> Result<u8, EvalError> {
+        match self.memory.get(&var) {
+            Some(e) => Ok(*e),
+            None if self.io.is_some() => Ok(0),
+            None => Err(EvalError::UnknownVariable(var)),
+        }
+    }
+
+    fn value(&self, value: &AsmValue) -> Result<u8, EvalError> {
+        match value {
+            AsmValue::Var(a) => self.read(a.0),
+            AsmValue::Number(a) => Ok(a.0),
+        }
+    }
+}
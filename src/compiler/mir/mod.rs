@@ -1,20 +1,28 @@
 use std::fmt::Display;
 
-use crate::compiler::asm::LabelType;
+use crate::compiler::{
+    asm::LabelType,
+    error::{CError, CErrorType},
+    type_defs::Result,
+};
 
+pub mod interpreter;
 pub mod optimizer;
 
 use super::asm::{AsmValue, CompilableInstruction, Label, Number, Var};
 
 #[derive(PartialEq, Clone, Hash)]
 pub enum Mir {
-    Copy(Var, AsmValue),                  // to, from - from isn't mutated
-    Increment(Var),                       // in, in is mutated
-    Decrement(Var),                       // in, in is mutated
-    If0(Var, MirCodeBlock, MirCodeBlock), // Jumps to the label if the thing is equals to 0
+    Copy(Var, AsmValue),                        // to, from - from isn't mutated
+    Increment(Var),                             // in, in is mutated
+    Decrement(Var),                             // in, in is mutated
+    If0(Var, MirCodeBlock, MirCodeBlock),       // Jumps to the label if the thing is equals to 0
+    Switch(Var, Vec<usize>, Vec<MirCodeBlock>), // Executes the case at the index the table has for the value
     Loop(MirCodeBlock),
-    Break,
-    Continue,
+    Break(usize),        // The number of loops around the one to leave
+    Continue(usize),     // The number of loops around the one to start again
+    Block(MirCodeBlock), // A function body which can be left with `Exit`
+    Exit(usize),         // The number of blocks around the one to leave
     Stop,
     ReadRegister(Var, Number),
     WriteRegister(Number, AsmValue),
@@ -49,6 +57,30 @@ impl Display for Mir {
                     .join("\n")
                     .replace("\n", "\n  ")
             ),
+            Mir::Switch(a, b, c) => write!(
+                f,
+                "switch v{} {{\n  {}\n}}",
+                a.0,
+                c.iter()
+                    .enumerate()
+                    .map(|(i, x)| format!(
+                        "{} => {{\n  {}\n}}",
+                        b.iter()
+                            .enumerate()
+                            .filter(|(_, x)| **x == i)
+                            .map(|(k, _)| k.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                        x.0.iter()
+                            .map(|x| x.to_string())
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                            .replace("\n", "\n  ")
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .replace("\n", "\n  ")
+            ),
             Mir::Loop(a) => write!(
                 f,
                 "loop {{\n  {}\n}}",
@@ -58,8 +90,21 @@ impl Display for Mir {
                     .join("\n")
                     .replace("\n", "\n  ")
             ),
-            Mir::Break => write!(f, "break"),
-            Mir::Continue => write!(f, "continue"),
+            Mir::Block(a) => write!(
+                f,
+                "block {{\n  {}\n}}",
+                a.0.iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .replace("\n", "\n  ")
+            ),
+            Mir::Exit(0) => write!(f, "exit"),
+            Mir::Exit(a) => write!(f, "exit {}", a),
+            Mir::Break(0) => write!(f, "break"),
+            Mir::Break(a) => write!(f, "break {}", a),
+            Mir::Continue(0) => write!(f, "continue"),
+            Mir::Continue(a) => write!(f, "continue {}", a),
             Mir::Stop => write!(f, "stop"),
             Mir::ReadRegister(a, b) => write!(f, "v{} = @{}", a.0, b.0),
             Mir::WriteRegister(a, b) => write!(
@@ -82,14 +127,14 @@ impl MirCodeBlock {
     pub fn push(&mut self, mir: Mir) {
         self.0.push(mir);
     }
-    pub fn to_asm(&self, state: &mut MirState) -> SkipStatus {
+    pub fn to_asm(&self, state: &mut MirState) -> Result<SkipStatus> {
         for i in &self.0 {
-            match i.to_asm(state) {
+            match i.to_asm(state)? {
                 SkipStatus::None => (),
-                e => return e,
+                e => return Ok(e),
             }
         }
-        SkipStatus::None
+        Ok(SkipStatus::None)
     }
 }
 
@@ -98,6 +143,7 @@ pub struct MirState {
     pub count: usize,
     pub instructions: Vec<CompilableInstruction>,
     loops: Vec<Label>,
+    blocks: Vec<Label>,
 }
 
 impl MirState {
@@ -162,12 +208,14 @@ impl SkipStatus {
 }
 
 impl Mir {
-    pub fn to_asm(&self, state: &mut MirState) -> SkipStatus {
+    /// Lowers the instruction, a `break`, `continue` or `return` outside of its loop or block is
+    /// an error.
+    pub fn to_asm(&self, state: &mut MirState) -> Result<SkipStatus> {
         match self {
             Mir::Copy(a, b) => {
                 if let AsmValue::Var(b) = b {
                     if a == b {
-                        return SkipStatus::None;
+                        return Ok(SkipStatus::None);
                     }
                 }
                 state.copy(a.clone(), b.clone())
@@ -181,18 +229,48 @@ impl Mir {
                 let end = Label::alloc(state, crate::compiler::asm::LabelType::IfEnd);
                 if b.0.is_empty() {
                     state.if0(a.clone(), end.clone());
-                    b.to_asm(state);
+                    c.to_asm(state)?;
                     state.label(end);
                 } else {
                     let start = end.derive(LabelType::IfStart);
                     state.if0(a.clone(), start.clone());
-                    let if1 = c.to_asm(state);
+                    let if1 = c.to_asm(state)?;
                     state.jump(end.clone());
                     state.label(start);
-                    let if2 = b.to_asm(state);
+                    let if2 = b.to_asm(state)?;
                     state.label(end);
-                    return if1.lightest(&if2);
+                    return Ok(if1.lightest(&if2));
+                }
+            }
+            Mir::Switch(a, b, c) => {
+                let end = Label::alloc(state, LabelType::SwitchEnd);
+                // Identical cases share their code
+                let mut labels: Vec<Label> = Vec::new();
+                for (i, case) in c.iter().enumerate() {
+                    labels.push(match c[..i].iter().position(|x| x == case) {
+                        Some(e) => labels[e].clone(),
+                        None => Label::alloc(state, LabelType::SwitchCase),
+                    });
+                }
+                state.instructions.push(CompilableInstruction::Switch(
+                    a.clone(),
+                    b.iter().map(|x| labels[*x].clone()).collect(),
+                ));
+                let mut status: Option<SkipStatus> = None;
+                for (i, case) in c.iter().enumerate() {
+                    if !b.contains(&i) || labels[..i].contains(&labels[i]) {
+                        continue;
+                    }
+                    state.label(labels[i].clone());
+                    let k = case.to_asm(state)?;
+                    state.jump(end.clone());
+                    status = Some(match status {
+                        Some(e) => e.lightest(&k),
+                        None => k,
+                    });
                 }
+                state.label(end);
+                return Ok(status.unwrap_or(SkipStatus::None));
             }
             Mir::Loop(a) => {
                 // If this happens this means the program will do nothing forever.
@@ -200,35 +278,69 @@ impl Mir {
                     let looplabel = Label::alloc(state, crate::compiler::asm::LabelType::LoopStart);
                     state.label(looplabel.clone());
                     state.jump(looplabel);
-                    return SkipStatus::Stoped;
+                    return Ok(SkipStatus::Stoped);
                 }
                 let loopstart = Label::alloc(state, crate::compiler::asm::LabelType::LoopStart);
                 let loopend = loopstart.derive(crate::compiler::asm::LabelType::LoopEnd);
                 state.label(loopstart.clone());
                 state.loops.push(loopstart.clone());
-                let k = a.to_asm(state);
+                let k = a.to_asm(state)?;
                 state.loops.pop();
                 state.jump(loopstart);
                 state.label(loopend);
                 if matches!(k, SkipStatus::Stoped) {
-                    return SkipStatus::Stoped;
+                    return Ok(SkipStatus::Stoped);
                 }
             }
-            Mir::Break => {
-                state.jump(state.loops.last().unwrap().derive(LabelType::LoopEnd)); // TODO: Add error here
-                return SkipStatus::Break;
+            Mir::Break(a) => {
+                // `break` and `continue` are only emitted in their loop, see `exit_loop`
+                let target = state
+                    .loops
+                    .iter()
+                    .rev()
+                    .nth(*a)
+                    .ok_or_else(|| CError(vec![], CErrorType::InvalidBreakOrContinue))?;
+                state.jump(target.derive(LabelType::LoopEnd));
+                return Ok(SkipStatus::Break);
+            }
+            Mir::Continue(a) => {
+                let target = state
+                    .loops
+                    .iter()
+                    .rev()
+                    .nth(*a)
+                    .ok_or_else(|| CError(vec![], CErrorType::InvalidBreakOrContinue))?;
+                state.jump(target.derive(LabelType::LoopStart));
+                return Ok(SkipStatus::Continue);
+            }
+            Mir::Block(a) => {
+                let end = Label::alloc(state, LabelType::FunctionEnd);
+                state.blocks.push(end.clone());
+                let k = a.to_asm(state)?;
+                state.blocks.pop();
+                state.label(end);
+                if matches!(k, SkipStatus::Stoped) {
+                    return Ok(SkipStatus::Stoped);
+                }
             }
-            Mir::Continue => {
-                state.jump(state.loops.last().unwrap().derive(LabelType::LoopStart)); // TODO: Add error here
-                return SkipStatus::Continue;
+            Mir::Exit(a) => {
+                // `return` is only emitted in its function, see `RETURN`
+                let target = state
+                    .blocks
+                    .iter()
+                    .rev()
+                    .nth(*a)
+                    .ok_or_else(|| CError(vec![], CErrorType::InvalidReturn))?;
+                state.jump(target.clone());
+                return Ok(SkipStatus::Break);
             }
             Mir::Stop => {
                 state.stop();
-                return SkipStatus::Stoped;
+                return Ok(SkipStatus::Stoped);
             }
             Mir::ReadRegister(a, b) => state.get_reg(a.clone(), b.clone()),
             Mir::WriteRegister(a, b) => state.set_reg(a.clone(), b.clone()),
         }
-        SkipStatus::None
+        Ok(SkipStatus::None)
     }
 }
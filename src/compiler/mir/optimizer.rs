@@ -5,7 +5,10 @@ use std::{
 
 use crate::compiler::asm::{AsmValue, Number, Var};
 
-use super::{Mir, MirCodeBlock};
+use super::{
+    interpreter::{Interpreter, FUEL},
+    Mir, MirCodeBlock,
+};
 
 trait Optimizer<T>: Sized {
     fn optimize(self, state: &mut T) -> Vec<Self>;
@@ -19,9 +22,12 @@ enum VarValue {
     Values(Vec<u8>),
 }
 
-fn intersect(values: &[u8], values1: &[u8]) -> Vec<u8> {
+/// The values a variable can have after one of two branches
+fn union(values: &[u8], values1: &[u8]) -> Vec<u8> {
     let mut v = values.to_vec();
-    values1.iter().for_each(|x| v.push(*x));
+    v.extend_from_slice(values1);
+    v.sort_unstable();
+    v.dedup();
     v
 }
 
@@ -31,6 +37,7 @@ impl VarValue {}
 struct OptimizerContext {
     used: HashSet<usize>,
     variables: HashMap<usize, VarValue>,
+    base: u8,
 }
 
 impl OptimizerContext {
@@ -49,6 +56,12 @@ impl OptimizerContext {
         self.variables.insert(var, value);
     }
 
+    /// The value after an increment (`1`) or a decrement (`-1`), variables wrap at `2^base`
+    fn wrap(&self, value: u8, step: i16) -> u8 {
+        let modulo = 1_i16 << self.base;
+        (value as i16 + step).rem_euclid(modulo) as u8
+    }
+
     pub fn get_raw_var(&self, var: usize) -> VarValue {
         self.variables
             .get(&var)
@@ -63,37 +76,6 @@ impl OptimizerContext {
         }
     }
 
-    /* pub fn add_values_to_var(&mut self, var: usize, values: &[u8]) {
-        match self.variables.get(&var) {
-            Some(a) => match a {
-                VarValue::VarRef(a) => {
-                    let a = self.get_var(*a);
-                    return self.set_var(
-                        var,
-                        match a {
-                            VarValue::VarRef(_) => unreachable!(),
-                            VarValue::Unknown => VarValue::Unknown,
-                            VarValue::Values(g) => VarValue::Values(intersect(&g, values)),
-                        },
-                    );
-                }
-                VarValue::Unknown => return,
-                VarValue::Values(a) => {
-                    self.set_var(var, VarValue::Values(intersect(a, values)));
-                }
-            },
-            None => return self.set_var(var, VarValue::Values(values.to_vec())),
-        }
-    }
-
-    fn get_var_meta_failible(&self, i: usize) -> VarValue {
-        match self.get_raw_var(i) {
-            VarValue::VarRef(a) => self.get_var_meta(a),
-            VarValue::Unknown => VarValue::Unknown,
-            VarValue::Values(a) => VarValue::Values(a),
-        }
-    } */
-
     fn get_var_meta(&self, i: usize) -> VarValue {
         match self.get_raw_var(i) {
             VarValue::VarRef(a) => self.get_var_meta(a),
@@ -120,16 +102,16 @@ impl OptimizerContext {
                 }
                 (VarValue::VarRef(_), VarValue::Values(b)) => {
                     if let VarValue::Values(a) = self.get_var(i) {
-                        map.insert(i, VarValue::Values(intersect(&a, &b)));
+                        map.insert(i, VarValue::Values(union(&a, &b)));
                     }
                 }
                 (VarValue::Values(a), VarValue::VarRef(_)) => {
                     if let VarValue::Values(b) = oc.get_var(i) {
-                        map.insert(i, VarValue::Values(intersect(&a, &b)));
+                        map.insert(i, VarValue::Values(union(&a, &b)));
                     }
                 }
                 (VarValue::Values(a), VarValue::Values(b)) => {
-                    map.insert(i, VarValue::Values(intersect(&a, &b)));
+                    map.insert(i, VarValue::Values(union(&a, &b)));
                 }
                 _ => (),
             }
@@ -137,8 +119,38 @@ impl OptimizerContext {
         Self {
             variables: map,
             used: self.used.clone(),
+            base: self.base,
         }
     }
+
+    /// Runs the loop if all the variables it uses have a known value and it doesn't leave the loops
+    /// around it, and returns the copies of the values it leaves.
+    fn fold(&self, block: &MirCodeBlock) -> Option<Vec<Mir>> {
+        let mut used = HashSet::new();
+        block.0.iter().for_each(|x| get_used(x, &mut used));
+        let muts = get_muts_cb(block);
+        let mut interpreter = Interpreter::new(self.base, FUEL);
+        for i in used.iter().chain(&muts) {
+            if let VarValue::Values(e) = self.get_var(*i) {
+                if e.len() == 1 {
+                    interpreter.memory.insert(*i, e[0]);
+                }
+            }
+        }
+        interpreter
+            .run(&MirCodeBlock(vec![Mir::Loop(block.clone())]))
+            .ok()?;
+        let mut muts: Vec<usize> = muts.into_iter().collect();
+        muts.sort_unstable();
+        Some(
+            muts.into_iter()
+                .filter_map(|x| {
+                    let value = interpreter.memory.get(&x)?;
+                    Some(Mir::Copy(Var(x), AsmValue::Number(Number(*value))))
+                })
+                .collect(),
+        )
+    }
 }
 
 impl Optimizer<OptimizerContext> for Mir {
@@ -162,13 +174,15 @@ impl Optimizer<OptimizerContext> for Mir {
                     },
                     e => e,
                 };
-                state.set_var(
-                    a.0,
-                    match b {
-                        crate::compiler::asm::AsmValue::Var(ab) => state.get_var_meta(ab.0),
-                        crate::compiler::asm::AsmValue::Number(ab) => VarValue::Values(vec![ab.0]),
-                    },
-                );
+                let value = match b {
+                    crate::compiler::asm::AsmValue::Var(ab) => state.get_var_meta(ab.0),
+                    crate::compiler::asm::AsmValue::Number(ab) => VarValue::Values(vec![ab.0]),
+                };
+                // The variable already has this value, keeping a reference to itself would loop
+                if matches!(value, VarValue::VarRef(e) if e == a.0) {
+                    return Vec::new();
+                }
+                state.set_var(a.0, value);
                 vec![Mir::Copy(a, bv)]
             }
             Mir::Increment(a) => {
@@ -177,18 +191,18 @@ impl Optimizer<OptimizerContext> for Mir {
                 }
                 if let VarValue::Values(d) = state.get_var(a.0) {
                     if d.len() == 1 {
-                        state.set_var(a.0, VarValue::Values(vec![(d[0] + 1) % 16]));
-                        return vec![Mir::Copy(a, AsmValue::Number(Number((d[0] + 1) % 16)))];
+                        let value = state.wrap(d[0], 1);
+                        state.set_var(a.0, VarValue::Values(vec![value]));
+                        return vec![Mir::Copy(a, AsmValue::Number(Number(value)))];
                     }
                 }
                 state.set_var(
                     a.0,
                     match state.get_var(a.0) {
-                        VarValue::VarRef(_) => unreachable!(),
-                        VarValue::Unknown => VarValue::Unknown,
                         VarValue::Values(a) => {
-                            VarValue::Values(a.iter().map(|x| (x + 1) % 16).collect())
+                            VarValue::Values(a.iter().map(|x| state.wrap(*x, 1)).collect())
                         }
+                        _ => VarValue::Unknown,
                     },
                 );
                 vec![Mir::Increment(a)]
@@ -199,18 +213,18 @@ impl Optimizer<OptimizerContext> for Mir {
                 }
                 if let VarValue::Values(d) = state.get_var(a.0) {
                     if d.len() == 1 {
-                        state.set_var(a.0, VarValue::Values(vec![(d[0] + 15) % 16]));
-                        return vec![Mir::Copy(a, AsmValue::Number(Number((d[0] + 15) % 16)))];
+                        let value = state.wrap(d[0], -1);
+                        state.set_var(a.0, VarValue::Values(vec![value]));
+                        return vec![Mir::Copy(a, AsmValue::Number(Number(value)))];
                     }
                 }
                 state.set_var(
                     a.0,
                     match state.get_var(a.0) {
-                        VarValue::VarRef(_) => unreachable!(),
-                        VarValue::Unknown => VarValue::Unknown,
                         VarValue::Values(a) => {
-                            VarValue::Values(a.iter().map(|x| (x + 15) % 16).collect())
+                            VarValue::Values(a.iter().map(|x| state.wrap(*x, -1)).collect())
                         }
+                        _ => VarValue::Unknown,
                     },
                 );
                 vec![Mir::Decrement(a)]
@@ -225,14 +239,14 @@ impl Optimizer<OptimizerContext> for Mir {
                         .collect();
                 }
                 if let VarValue::Values(d) = state.get_var(a.0) {
-                    if d.contains(&0) {
+                    if d.iter().all(|x| *x == 0) {
                         return b
                             .0
                             .into_iter()
                             .map(|x| x.optimize(state))
                             .flatten()
                             .collect();
-                    } else {
+                    } else if !d.contains(&0) {
                         return c
                             .0
                             .into_iter()
@@ -258,12 +272,11 @@ impl Optimizer<OptimizerContext> for Mir {
                     state.variables.insert(
                         a.0,
                         match state.get_var(a.0) {
-                            VarValue::VarRef(_) => unreachable!(),
-                            VarValue::Unknown => VarValue::Values((1..16).collect()),
                             VarValue::Values(mut a) => {
-                                a.remove(0);
+                                a.retain(|x| *x != 0);
                                 VarValue::Values(a)
                             }
+                            _ => VarValue::Values((1..=state.wrap(0, -1)).collect()),
                         },
                     );
                     (
@@ -278,10 +291,65 @@ impl Optimizer<OptimizerContext> for Mir {
 
                 return vec![Mir::If0(a, MirCodeBlock(k1), MirCodeBlock(k2))];
             }
+            Mir::Switch(a, b, c) => {
+                let known = match state.get_var(a.0) {
+                    VarValue::Values(d) => Some(d),
+                    _ => None,
+                };
+                // The values leading to each case
+                let values: Vec<Vec<u8>> = (0..c.len())
+                    .map(|i| {
+                        b.iter()
+                            .enumerate()
+                            .filter(|(_, x)| **x == i)
+                            .map(|(k, _)| k as u8)
+                            .filter(|k| known.as_ref().map(|d| d.contains(k)).unwrap_or(true))
+                            .collect()
+                    })
+                    .collect();
+                let mut states = Vec::new();
+                let c: Vec<MirCodeBlock> = c
+                    .into_iter()
+                    .zip(&values)
+                    .map(|(case, values)| {
+                        if values.is_empty() {
+                            return MirCodeBlock(vec![]);
+                        }
+                        let mut state = state.clone();
+                        state
+                            .variables
+                            .insert(a.0, VarValue::Values(values.clone()));
+                        let k = case
+                            .0
+                            .into_iter()
+                            .flat_map(|x| x.optimize(&mut state))
+                            .collect();
+                        states.push(state);
+                        MirCodeBlock(k)
+                    })
+                    .collect();
+                let mut reached = values.iter().enumerate().filter(|(_, x)| !x.is_empty());
+                match (reached.next(), reached.next()) {
+                    (None, _) => return vec![],
+                    (Some((i, _)), None) => {
+                        *state = states.remove(0);
+                        return c.into_iter().nth(i).unwrap().0;
+                    }
+                    _ => (),
+                }
+                *state = states[1..]
+                    .iter()
+                    .fold(states[0].clone(), |x, y| x.merge(y));
+                vec![Mir::Switch(a, b, c)]
+            }
             // TODO: Set the vars to be thoses of the last iteration (Where break blocks are)
             Mir::Loop(a) => {
+                if let Some(e) = state.fold(&a) {
+                    return e.into_iter().flat_map(|x| x.optimize(state)).collect();
+                }
+                // `set_var` also forgets the copies referencing the previous values
                 for i in get_muts_cb(&a) {
-                    state.variables.remove(&i);
+                    state.set_var(i, VarValue::Unknown);
                 }
                 let mut k = state.clone();
                 return vec![Mir::Loop(MirCodeBlock(
@@ -291,8 +359,18 @@ impl Optimizer<OptimizerContext> for Mir {
                         .collect(),
                 ))];
             }
-            Mir::Break => vec![Mir::Break],
-            Mir::Continue => vec![Mir::Continue],
+            // The block runs once from the start, but the values after it depend on where it exits
+            Mir::Block(a) => {
+                let muts = get_muts_cb(&a);
+                let code = a.0.into_iter().flat_map(|x| x.optimize(state)).collect();
+                for i in muts {
+                    state.set_var(i, VarValue::Unknown);
+                }
+                vec![Mir::Block(MirCodeBlock(code))]
+            }
+            Mir::Exit(a) => vec![Mir::Exit(a)],
+            Mir::Break(a) => vec![Mir::Break(a)],
+            Mir::Continue(a) => vec![Mir::Continue(a)],
             Mir::Stop => vec![Mir::Stop],
             Mir::ReadRegister(a, b) => {
                 if !state.used.contains(&a.0) {
@@ -348,15 +426,15 @@ pub fn get_muts_cb(mir: &MirCodeBlock) -> HashSet<usize> {
     muts
 }
 
-pub fn opt(mir: Vec<Mir>) -> Vec<Mir> {
-    std::fs::write(
+pub fn opt(mir: Vec<Mir>, base: u8) -> Vec<Mir> {
+    // The dumps only help to debug the optimizer, `target` may not exist
+    let _ = std::fs::write(
         "target/before_opt.mir",
         mir.iter()
             .map(|x| x.to_string())
             .collect::<Vec<_>>()
             .join("\n"),
-    )
-    .unwrap();
+    );
     let c1 = mir.iter().map(count).sum::<usize>();
 
     let mut l: Vec<Mir> = mir;
@@ -382,6 +460,7 @@ pub fn opt(mir: Vec<Mir>) -> Vec<Mir> {
         let mut state = OptimizerContext {
             variables: HashMap::new(),
             used,
+            base,
         };
         l = l
             .into_iter()
@@ -394,14 +473,13 @@ pub fn opt(mir: Vec<Mir>) -> Vec<Mir> {
         }
         k = o;
     }
-    std::fs::write(
+    let _ = std::fs::write(
         "target/after_opt.mir",
         l.iter()
             .map(|x| x.to_string())
             .collect::<Vec<_>>()
             .join("\n"),
-    )
-    .unwrap();
+    );
     let c2 = l.iter().map(count).sum::<usize>();
     println!(
         "Optimized from {} MIR instructions to {} MIR instructions in {} iterations",
@@ -418,9 +496,16 @@ pub fn count(mir: &Mir) -> usize {
         Mir::If0(_, a, b) => {
             1 + a.0.iter().map(count).sum::<usize>() + b.0.iter().map(count).sum::<usize>()
         }
-        Mir::Loop(a) => 1 + a.0.iter().map(count).sum::<usize>(),
-        Mir::Break => 1,
-        Mir::Continue => 1,
+        Mir::Switch(_, _, a) => {
+            1 + a
+                .iter()
+                .map(|x| x.0.iter().map(count).sum::<usize>())
+                .sum::<usize>()
+        }
+        Mir::Loop(a) | Mir::Block(a) => 1 + a.0.iter().map(count).sum::<usize>(),
+        Mir::Exit(_) => 1,
+        Mir::Break(_) => 1,
+        Mir::Continue(_) => 1,
         Mir::Stop => 1,
         Mir::ReadRegister(_, _) => 1,
         Mir::WriteRegister(_, _) => 1,
@@ -436,7 +521,10 @@ pub fn get_muts(mir: &Mir, muts: &mut HashSet<usize>) {
             a.0.iter().for_each(|a| get_muts(a, muts));
             b.0.iter().for_each(|a| get_muts(a, muts));
         }
-        Mir::Loop(a) => a.0.iter().for_each(|a| get_muts(a, muts)),
+        Mir::Switch(_, _, a) => a
+            .iter()
+            .for_each(|a| a.0.iter().for_each(|a| get_muts(a, muts))),
+        Mir::Loop(a) | Mir::Block(a) => a.0.iter().for_each(|a| get_muts(a, muts)),
         _ => (),
     }
 }
@@ -451,7 +539,12 @@ pub fn get_used(mir: &Mir, muts: &mut HashSet<usize>) {
             a.0.iter().for_each(|a| get_used(a, muts));
             b.0.iter().for_each(|a| get_used(a, muts));
         }
-        Mir::Loop(a) => a.0.iter().for_each(|a| get_used(a, muts)),
+        Mir::Switch(c, _, a) => {
+            muts.insert(c.0);
+            a.iter()
+                .for_each(|a| a.0.iter().for_each(|a| get_used(a, muts)));
+        }
+        Mir::Loop(a) | Mir::Block(a) => a.0.iter().for_each(|a| get_used(a, muts)),
         _ => (),
     }
 }
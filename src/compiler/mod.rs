@@ -3,6 +3,7 @@ pub mod error;
 pub mod functions;
 pub mod mir;
 pub mod parser;
+pub mod resolver;
 pub mod scope;
 pub mod state;
 pub mod type_defs;
@@ -13,8 +13,6 @@ impl CodeBlock {
         state: &mut State,
         ss: &mut ScopedState,
     ) -> Result<Option<CVariable>> {
-        let return_var = state.count();
-        ss.return_to = return_var;
         let mut k = None;
         for m in &self.0 {
             k = m.execute(ss, state)?;
@@ -22,8 +20,6 @@ impl CodeBlock {
         Ok(k)
     }
     pub fn execute(&self, state: &mut State, mut ss: ScopedState) -> Result<Option<CVariable>> {
-        let return_var = state.count();
-        ss.return_to = return_var;
         let mut k = None;
         for m in &self.0 {
             k = m.execute(&mut ss, state)?;
@@ -0,0 +1,111 @@
+use pest::iterators::Pair;
+
+use crate::compiler::{error::CSpan, type_defs::Result};
+
+use super::{parse_pairs, Rule};
+
+/// A lossless syntax tree: unlike the AST it keeps the comments, and the text between
+/// the nodes (whitespace, `,`, `;`, parentheses...) can be recovered from the source.
+pub struct Cst {
+    pub source: String,
+    pub root: CstNode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CstKind {
+    File,
+    /// The first child is the name of the function
+    FunctionCall,
+    /// Children are the name and the value
+    NamedArgument,
+    /// `true` for `{...}` blocks, `false` for `:expr` blocks
+    CodeBlock(bool),
+    /// Children are the left operand, the operator and the right operand
+    Operation,
+    /// Children are the operator and the operand
+    UnaryOperation,
+    Literal,
+    Number,
+    /// A string or character literal
+    String,
+    Comment,
+}
+
+#[derive(Debug, Clone)]
+pub struct CstNode {
+    pub kind: CstKind,
+    pub start: usize,
+    pub end: usize,
+    pub children: Vec<CstNode>,
+}
+
+impl Cst {
+    pub fn parse(file_name: &str, source: String, span: Vec<CSpan>) -> Result<Self> {
+        let root = CstNode::from_pair(parse_pairs(file_name, source.clone(), span)?);
+        Ok(Self { source, root })
+    }
+
+    pub fn text(&self, node: &CstNode) -> &str {
+        &self.source[node.start..node.end]
+    }
+
+    /// Rebuilds the source from the tree, this is always equal to the parsed source.
+    pub fn to_source(&self) -> String {
+        let mut out = String::new();
+        self.root.write_source(&self.source, &mut out);
+        out
+    }
+}
+
+impl CstNode {
+    fn from_pair(pair: Pair<Rule>) -> Self {
+        let span = pair.as_span();
+        let (start, end) = (span.start(), span.end());
+        let kind = match pair.as_rule() {
+            Rule::file => CstKind::File,
+            Rule::function_call => CstKind::FunctionCall,
+            Rule::named_argument => CstKind::NamedArgument,
+            Rule::code_block => CstKind::CodeBlock(span.as_str().starts_with('{')),
+            Rule::opera2 => CstKind::Operation,
+            Rule::opera1 => CstKind::UnaryOperation,
+            Rule::literal => CstKind::Literal,
+            Rule::number => CstKind::Number,
+            Rule::string | Rule::character => CstKind::String,
+            Rule::COMMENT => CstKind::Comment,
+            // `expression` and `simple` only wrap a single node
+            Rule::expression | Rule::simple => {
+                return Self::from_pair(pair.into_inner().next().unwrap())
+            }
+            e => unreachable!("{:?}", e),
+        };
+        let children = match kind {
+            CstKind::Literal | CstKind::String | CstKind::Comment => Vec::new(),
+            _ => pair
+                .into_inner()
+                .filter(|x| !matches!(x.as_rule(), Rule::EOI | Rule::WHITESPACE))
+                .map(Self::from_pair)
+                .collect(),
+        };
+        Self {
+            kind,
+            start,
+            end,
+            children,
+        }
+    }
+
+    /// The children that aren't comments
+    pub fn nodes(&self) -> impl Iterator<Item = &CstNode> {
+        self.children.iter().filter(|x| x.kind != CstKind::Comment)
+    }
+
+    fn write_source(&self, source: &str, out: &mut String) {
+        let mut pos = self.start;
+        for i in &self.children {
+            out.push_str(&source[pos..i.start]);
+            i.write_source(source, out);
+            pos = i.end;
+        }
+        out.push_str(&source[pos..self.end]);
+    }
+}
@@ -1,11 +1,14 @@
 use crate::compiler::{
     asm::{AsmValue, Number, Var},
     error::{CError, CErrorType, CSpan},
+    functions::fn_infix::Associativity,
     scope::ScopedState,
     state::State,
-    variable::CVariable,
+    variable::{cell_number, CVariable},
 };
 
+use std::iter::Peekable;
+
 use super::{codeblock::CodeBlock, function_call::FunctionCall};
 
 use crate::compiler::type_defs::Result;
@@ -16,6 +19,13 @@ pub enum Expression {
     CodeBlock(CSpan, CodeBlock),
     Literal(CSpan, String),
     Number(CSpan, u8),
+    /// A character literal, its ASCII code has to fit in a cell unless it is printed
+    Character(CSpan, u8),
+    /// The text without quotes, with the escapes already replaced
+    String(CSpan, String),
+    /// A chain of binary operators, the operands are grouped with the precedence of the operators
+    /// in the scope where the chain is used
+    Operation(CSpan, Vec<Expression>, Vec<(CSpan, String)>),
 }
 
 impl Expression {
@@ -61,8 +71,17 @@ impl Expression {
         match self {
             Expression::FunctionCall(_s, m) => ss.execute(m, state),
             Expression::CodeBlock(_s, m) => m.execute(state, ss.clone()),
-            Expression::Literal(s, m) => Ok(ss.get_variable(&[s.clone()], m)?.unroll(state)?),
+            Expression::Literal(s, m) => match ss.get_field_path(s, m, state)? {
+                Some(e) => e.unroll(state),
+                None => ss.get_variable(&[s.clone()], m)?.unroll(state),
+            },
             Expression::Number(s, a) => Ok(Some(CVariable::Number(vec![s.clone()], *a))),
+            Expression::Character(s, a) => Ok(Some(cell_number(state, s, *a as usize)?)),
+            Expression::String(s, _) => Err(CError(
+                vec![s.clone()],
+                CErrorType::StringUsedAsVariableInInvalidContext,
+            )),
+            Expression::Operation(..) => self.group_operators(ss).execute(ss, state),
         }
     }
 
@@ -82,6 +101,18 @@ impl Expression {
                     CErrorType::StructUsedAsVariableInInvalidContext(b.name),
                 ))
             }
+            CVariable::Array(a, _) => {
+                return Err(CError(a, CErrorType::ArrayUsedAsVariableInInvalidContext))
+            }
+            CVariable::List(a, _) => {
+                return Err(CError(a, CErrorType::ListUsedAsVariableInInvalidContext))
+            }
+            CVariable::Function(a, _) => {
+                return Err(CError(
+                    a,
+                    CErrorType::FunctionUsedAsVariableInInvalidContext,
+                ))
+            }
         })
     }
 
@@ -99,13 +130,30 @@ impl Expression {
                 .execute(state, ss.clone())?
                 .ok_or_else(|| CError(vec![s.clone()], CErrorType::ExpectedVariable))?,
             Expression::Literal(s, a) => {
-                if declare {
+                if let Some(e) = ss.get_field_path(s, a, state)? {
+                    e
+                } else if let (false, false, Some(e)) =
+                    (declare, ss.variables.contains_key(a), ss.get_function(a))
+                {
+                    // The name of a function is a value that can be passed around
+                    CVariable::Function(vec![s.clone()], e.clone())
+                } else if declare {
                     ss.get_or_declare_variable(a, s, state)
                 } else {
                     ss.get_variable(&[s.clone()], a)?.clone()
                 }
             }
             Expression::Number(s, a) => CVariable::Number(vec![s.clone()], *a),
+            Expression::Character(s, a) => cell_number(state, s, *a as usize)?,
+            Expression::String(s, _) => {
+                return Err(CError(
+                    vec![s.clone()],
+                    CErrorType::StringUsedAsVariableInInvalidContext,
+                ))
+            }
+            Expression::Operation(..) => {
+                return self.group_operators(ss).get_value(ss, state, declare)
+            }
         })
     }
 
@@ -114,7 +162,56 @@ impl Expression {
             Expression::FunctionCall(a, _)
             | Expression::CodeBlock(a, _)
             | Expression::Literal(a, _)
-            | Expression::Number(a, _) => a,
+            | Expression::Number(a, _)
+            | Expression::Character(a, _)
+            | Expression::String(a, _)
+            | Expression::Operation(a, ..) => a,
+        }
+    }
+
+    /// Turns an operation in nested function calls. `pest::prec_climber` can't be used as the
+    /// operators are only known while compiling.
+    pub fn group_operators(&self, ss: &ScopedState) -> Expression {
+        match self {
+            Expression::Operation(_, a, b) => climb(ss, &mut a.iter(), &mut b.iter().peekable(), 0),
+            e => e.clone(),
+        }
+    }
+}
+
+/// Precedence climbing: groups the operators binding at least as tightly as `min`.
+fn climb<'a>(
+    ss: &ScopedState,
+    operands: &mut impl Iterator<Item = &'a Expression>,
+    operators: &mut Peekable<impl Iterator<Item = &'a (CSpan, String)>>,
+    min: u16,
+) -> Expression {
+    let mut left = operands.next().unwrap().clone();
+    while let Some(&(_, name)) = operators.peek() {
+        let operator = ss.get_operator(name);
+        if (operator.precedence as u16) < min {
+            break;
         }
+        operators.next();
+        let next = match operator.associativity {
+            Associativity::Left => operator.precedence as u16 + 1,
+            Associativity::Right => operator.precedence as u16,
+        };
+        let right = climb(ss, operands, operators, next);
+        let (start, end) = (left.get_span(), right.get_span());
+        let span = CSpan::new(
+            start.filename.clone(),
+            start.span.start_pos().span(&end.span.end_pos()),
+        );
+        left = Expression::FunctionCall(
+            span.clone(),
+            FunctionCall {
+                name: name.clone(),
+                arguments: vec![left, right],
+                named_arguments: vec![],
+                span,
+            },
+        );
     }
+    left
 }
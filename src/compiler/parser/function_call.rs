@@ -7,4 +7,19 @@ pub struct FunctionCall {
     pub name: String,
     pub span: CSpan,
     pub arguments: Vec<Expression>,
+    pub named_arguments: Vec<NamedArgument>,
+}
+
+/// An argument passed as `name: value`
+#[derive(Debug, Clone)]
+pub struct NamedArgument {
+    pub name: String,
+    pub span: CSpan,
+    pub value: Expression,
+}
+
+impl FunctionCall {
+    pub fn get_named_argument(&self, name: &str) -> Option<&NamedArgument> {
+        self.named_arguments.iter().find(|x| x.name == name)
+    }
 }
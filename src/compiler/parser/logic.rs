@@ -8,19 +8,31 @@ use crate::compiler::parser::{expression::Expression, Rule};
 
 use crate::compiler::type_defs::Result;
 
-use super::{function_call::FunctionCall, Parse};
+use super::{
+    function_call::{FunctionCall, NamedArgument},
+    inner, Parse,
+};
 
 impl Parse for FunctionCall {
     fn from_pairs(pair: Pair<Rule>, file: &Rc<String>) -> Result<Self> {
         match pair.as_rule() {
             Rule::function_call => {
                 let span = pair.as_span();
-                let mut i = pair.into_inner();
+                let mut i = inner(pair);
+                let name = i.next().unwrap().as_str().trim().to_owned();
+                let mut arguments = Vec::new();
+                let mut named_arguments = Vec::new();
+                for x in i {
+                    if x.as_rule() == Rule::named_argument {
+                        named_arguments.push(NamedArgument::from_pairs(x, file)?);
+                    } else {
+                        arguments.push(Expression::from_pairs(x, file)?);
+                    }
+                }
                 Ok(Self {
-                    name: i.next().unwrap().as_str().trim().to_owned(),
-                    arguments: i
-                        .map(|x| Expression::from_pairs(x, file))
-                        .collect::<Result<Vec<_>>>()?,
+                    name,
+                    arguments,
+                    named_arguments,
                     span: CSpan::new(file.clone(), span),
                 })
             }
@@ -29,13 +41,25 @@ impl Parse for FunctionCall {
     }
 }
 
+impl Parse for NamedArgument {
+    fn from_pairs(pair: Pair<Rule>, file: &Rc<String>) -> Result<Self> {
+        let span = pair.as_span();
+        let mut i = inner(pair);
+        Ok(Self {
+            name: i.next().unwrap().as_str().trim().to_owned(),
+            value: Expression::from_pairs(i.next().unwrap(), file)?,
+            span: CSpan::new(file.clone(), span),
+        })
+    }
+}
+
 impl Parse for Expression {
     fn from_pairs(pair: Pair<Rule>, file: &Rc<String>) -> Result<Self> {
         match pair.as_rule() {
-            Rule::simple => Self::from_pairs(pair.into_inner().next().unwrap(), file),
+            Rule::simple => Self::from_pairs(inner(pair).next().unwrap(), file),
             Rule::opera1 => {
                 let span = pair.as_span();
-                let mut i = pair.into_inner();
+                let mut i = inner(pair);
                 let operator = i.next().unwrap();
                 let operator = operator.as_str().trim();
                 let expr = Self::from_pairs(i.next().unwrap(), file)?;
@@ -44,27 +68,52 @@ impl Parse for Expression {
                     FunctionCall {
                         name: format!("{}_unique", operator),
                         arguments: vec![expr],
+                        named_arguments: vec![],
                         span: CSpan::new(file.clone(), span),
                     },
                 ))
             }
             Rule::opera2 => {
                 let span = pair.as_span();
-                let mut i = pair.into_inner();
-                let expr = Self::from_pairs(i.next().unwrap(), file)?;
-                let operator = i.next().unwrap();
-                let operator = operator.as_str().trim();
-                let expr1 = Self::from_pairs(i.next().unwrap(), file)?;
-                Ok(Self::FunctionCall(
-                    CSpan::new(file.clone(), span.clone()),
-                    FunctionCall {
-                        name: operator.to_string(),
-                        arguments: vec![expr, expr1],
-                        span: CSpan::new(file.clone(), span),
-                    },
-                ))
+                let mut operands = Vec::new();
+                let mut operators = Vec::new();
+                let mut pair = pair;
+                // `a + b * c` is parsed as `a + (b * c)`, the chain is flattened so the
+                // precedence of the operators can be applied when it is used
+                loop {
+                    let mut i = inner(pair);
+                    operands.push(Self::from_pairs(i.next().unwrap(), file)?);
+                    let operator = i.next().unwrap();
+                    operators.push((
+                        CSpan::new(file.clone(), operator.as_span()),
+                        operator.as_str().trim().to_owned(),
+                    ));
+                    let right = i.next().unwrap();
+                    let right = match inner(right.clone()).next() {
+                        Some(e) if e.as_rule() == Rule::opera2 => e,
+                        _ => {
+                            operands.push(Self::from_pairs(right, file)?);
+                            break;
+                        }
+                    };
+                    pair = right;
+                }
+                let span = CSpan::new(file.clone(), span);
+                Ok(if operators.len() == 1 {
+                    Self::FunctionCall(
+                        span.clone(),
+                        FunctionCall {
+                            name: operators.remove(0).1,
+                            arguments: operands,
+                            named_arguments: vec![],
+                            span,
+                        },
+                    )
+                } else {
+                    Self::Operation(span, operands, operators)
+                })
             }
-            Rule::expression => Self::from_pairs(pair.into_inner().next().unwrap(), file),
+            Rule::expression => Self::from_pairs(inner(pair).next().unwrap(), file),
             Rule::literal => Ok(Self::Literal(
                 CSpan::new(file.clone(), pair.as_span()),
                 pair.as_str().trim().to_owned(),
@@ -78,6 +127,18 @@ impl Parse for Expression {
                     )
                 })?,
             )),
+            Rule::string => {
+                let span = CSpan::new(file.clone(), pair.as_span());
+                let text = pair.as_str().trim();
+                let text = unescape(&text[1..text.len() - 1], &span)?;
+                Ok(Self::String(span, text))
+            }
+            Rule::character => {
+                let span = CSpan::new(file.clone(), pair.as_span());
+                let text = pair.as_str().trim();
+                let text = unescape(&text[1..text.len() - 1], &span)?;
+                Ok(Self::Character(span, text.as_bytes()[0]))
+            }
             Rule::function_call => {
                 let span = pair.as_span();
                 FunctionCall::from_pairs(pair, file)
@@ -86,7 +147,7 @@ impl Parse for Expression {
             Rule::code_block => Ok(Self::CodeBlock(
                 CSpan::new(file.clone(), pair.as_span()),
                 CodeBlock(
-                    pair.into_inner()
+                    inner(pair)
                         .map(|x| Expression::from_pairs(x, file))
                         .collect::<Result<Vec<_>>>()?,
                 ),
@@ -95,3 +156,34 @@ impl Parse for Expression {
         }
     }
 }
+
+/// Replaces the escapes of string and character literals, only ASCII text is allowed as the
+/// characters are printed one byte at a time.
+fn unescape(text: &str, span: &CSpan) -> Result<String> {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let c = if c == '\\' {
+            match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some(e @ ('\\' | '"' | '\'')) => e,
+                e => {
+                    return Err(CError(
+                        vec![span.clone()],
+                        CErrorType::InvalidEscape(e.map(String::from).unwrap_or_default()),
+                    ))
+                }
+            }
+        } else {
+            c
+        };
+        if !c.is_ascii() {
+            return Err(CError(vec![span.clone()], CErrorType::NonAsciiCharacter(c)));
+        }
+        out.push(c);
+    }
+    Ok(out)
+}
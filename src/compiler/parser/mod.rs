@@ -12,6 +12,7 @@ use self::expression::Expression;
 use super::error::CSpan;
 
 pub mod codeblock;
+pub mod cst;
 pub mod expression;
 pub mod function_call;
 pub mod logic;
@@ -24,14 +25,15 @@ trait Parse: Sized {
     fn from_pairs(pair: Pair<Rule>, file: &Rc<String>) -> Result<Self>;
 }
 
-pub fn parse_file(
-    file_name: &str,
-    file_content: String,
-    span: Vec<CSpan>,
-) -> Result<Vec<Expression>> {
+/// Comments are kept in the parse tree for the formatter, the AST skips them.
+fn inner(pair: Pair<Rule>) -> impl Iterator<Item = Pair<Rule>> {
+    pair.into_inner().filter(|x| x.as_rule() != Rule::COMMENT)
+}
+
+fn parse_pairs(file_name: &str, file_content: String, span: Vec<CSpan>) -> Result<Pair<Rule>> {
     let unparsed_file = Rc::new(file_content);
 
-    let file = match CythanParser::parse(Rule::file, unparsed_file) {
+    Ok(match CythanParser::parse(Rule::file, unparsed_file) {
         Ok(e) => e,
         Err(e) => {
             return Err(CError(
@@ -45,13 +47,21 @@ pub fn parse_file(
         }
     } // unwrap the parse result
     .next()
-    .unwrap(); // get and unwrap the `file` rule; never fails
+    .unwrap()) // get and unwrap the `file` rule; never fails
+}
+
+pub fn parse_file(
+    file_name: &str,
+    file_content: String,
+    span: Vec<CSpan>,
+) -> Result<Vec<Expression>> {
+    let file = parse_pairs(file_name, file_content, span.clone())?;
 
     let file1 = Rc::new(file_name.to_owned());
 
     match file
         .into_inner()
-        .filter(|x| !matches!(x.as_rule(), Rule::EOI | Rule::WHITESPACE))
+        .filter(|x| !matches!(x.as_rule(), Rule::EOI | Rule::WHITESPACE | Rule::COMMENT))
         .map(|x| Expression::from_pairs(x, &file1))
         .collect::<Result<Vec<_>>>()
     {
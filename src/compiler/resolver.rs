@@ -0,0 +1,79 @@
+//! Resolution of the files loaded by `include` and `import`.
+//! A file is searched:
+//! - relative to the file containing the call
+//! - in the `-I` search paths, then in the `CYTHAN_PATH` ones
+//! - in the standard library bundled in the compiler, as `std/<file>`
+//!
+//! The `.ct1` extension can be omitted and `std` alone is the standard library of the
+//! current base.
+
+use std::path::{Path, PathBuf};
+
+use super::{
+    error::{CErrorType, CSpan},
+    state::State,
+};
+
+/// Prefix of the paths of the bundled files
+const BUNDLED_PREFIX: &str = "<std>";
+
+const BUNDLED: &[(&str, &str)] = &[
+    ("base4.ct1", include_str!("../std/base4.ct1")),
+    ("names.ct1", include_str!("../std/names.ct1")),
+];
+
+/// The search paths given by the `CYTHAN_PATH` environment variable
+pub fn env_paths() -> Vec<PathBuf> {
+    std::env::var_os("CYTHAN_PATH")
+        .map(|x| std::env::split_paths(&x).collect())
+        .unwrap_or_default()
+}
+
+pub fn resolve_file(state: &State, span: &CSpan, file: &str) -> Option<PathBuf> {
+    let mut path = Path::new(span.get_filename()).to_path_buf();
+    path.pop();
+    let file = if file == "std" {
+        format!("std/base{}", state.base)
+    } else {
+        file.to_owned()
+    };
+    std::iter::once(path)
+        .chain(state.include_paths.iter().cloned())
+        .map(|x| x.join(&file))
+        .chain(
+            file.strip_prefix("std/")
+                .map(|x| Path::new(BUNDLED_PREFIX).join(x)),
+        )
+        .flat_map(|x| {
+            let with_extension = x.extension().is_none().then(|| x.with_extension("ct1"));
+            std::iter::once(x).chain(with_extension)
+        })
+        .find(|x| bundled(x).is_some() || x.is_file())
+}
+
+/// The error reported when `file` can't be resolved
+pub fn not_found(state: &State, file: &str) -> CErrorType {
+    if file == "std" {
+        CErrorType::NoBundledStd(state.base)
+    } else {
+        CErrorType::FileNotFound(file.to_owned())
+    }
+}
+
+/// Reads a file from the disk or from the bundled files
+pub fn read_source(file: &str) -> Option<String> {
+    match bundled(Path::new(file)) {
+        Some(e) => Some(e.to_owned()),
+        None => std::fs::read_to_string(file).ok(),
+    }
+}
+
+/// A path identifying the file, used to load imported files once
+pub fn canonicalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn bundled(path: &Path) -> Option<&'static str> {
+    let name = path.strip_prefix(BUNDLED_PREFIX).ok()?.to_str()?;
+    BUNDLED.iter().find(|x| x.0 == name).map(|x| x.1)
+}
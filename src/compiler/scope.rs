@@ -3,12 +3,40 @@ use std::{collections::HashMap, rc::Rc};
 use super::{
     error::{CError, CSpan},
     functions::{
-        fn_break::BREAK, fn_continue::CONTINUE, fn_dec::DEC, fn_exit::EXIT, fn_fn::FN,
-        fn_get_field::GET_FIELD, fn_get_reg::GET_REG, fn_if0::IF0, fn_inc::INC,
-        fn_include::INCLUDE, fn_let::LET, fn_loop::LOOP, fn_set::SET, fn_set_reg::SET_REG,
+        fn_array::{ARRAY, GET, LEN},
+        fn_assert::{ASSERT, ASSERT_EQ},
+        fn_bool::{AND, NOT, OR},
+        fn_break::BREAK,
+        fn_cmp::{EQ, GE, GT, LE, LT, NE},
+        fn_const::CONST,
+        fn_continue::CONTINUE,
+        fn_dec::DEC,
+        fn_exit::EXIT,
+        fn_export::EXPORT,
+        fn_fn::{bind_arguments, Parameter, FN},
+        fn_for_each::FOR_EACH,
+        fn_get_field::GET_FIELD,
+        fn_get_reg::GET_REG,
+        fn_if0::IF0,
+        fn_import::IMPORT,
+        fn_inc::INC,
+        fn_include::INCLUDE,
+        fn_infix::{Associativity, Operator, INFIX},
+        fn_lambda::LAMBDA,
+        fn_let::LET,
+        fn_loop::LOOP,
+        fn_macro::MACRO,
+        fn_match::MATCH,
+        fn_print_str::PRINT_STR,
+        fn_read::{EOF, READ_CHAR, READ_DIGIT},
+        fn_return::RETURN,
+        fn_set::SET,
+        fn_set_field::SET_FIELD,
+        fn_set_reg::SET_REG,
         fn_struct::STRUCT,
+        fn_test::TEST,
     },
-    parser::function_call::FunctionCall,
+    parser::{expression::Expression, function_call::FunctionCall},
     state::State,
     type_defs::Handler,
     variable::CVariable,
@@ -16,13 +44,43 @@ use super::{
 
 use crate::compiler::{error::CErrorType, type_defs::Result};
 
+/// A definition of a function, a name can have one per number of arguments
+#[derive(Clone)]
+pub struct Overload {
+    pub handler: Rc<Handler>,
+    /// `None` for builtins and constructors, they accept any arguments and check them themselves
+    pub arguments: Option<Vec<Parameter>>,
+}
+
+impl Overload {
+    fn signature(&self, name: &str) -> String {
+        match &self.arguments {
+            Some(e) => format!(
+                "{}({})",
+                name,
+                e.iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            None => format!("{}(...)", name),
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct ScopedState {
-    pub current_loop: Option<()>, // (start, end)
+    /// The loops around the code, innermost last: their label and their id in `State::loops`
+    pub loops: Vec<(Option<String>, usize)>,
     pub variables: HashMap<String, CVariable>,
     call_graph: Vec<String>,
-    functions: HashMap<String, Rc<Handler>>,
-    pub return_to: usize,
+    functions: HashMap<String, Vec<Overload>>,
+    /// The return cell of the function the code is written in, `None` outside of functions
+    pub return_to: Option<usize>,
+    /// Functions listed by `export`, `None` exports every function defined in the file
+    pub exports: Option<Vec<(CSpan, String)>>,
+    /// The operators declared with `infix`
+    pub operators: HashMap<String, Operator>,
 }
 
 impl ScopedState {
@@ -30,19 +88,64 @@ impl ScopedState {
         let mut k = Self::default();
         k.add_function("exit", EXIT);
         k.add_function("fn", FN);
+        k.add_function("macro", MACRO);
         k.add_function("set", SET);
+        k.add_function("=", SET);
         k.add_function("if0", IF0);
         k.add_function("set_reg", SET_REG);
         k.add_function("get_reg", GET_REG);
         k.add_function("loop", LOOP);
         k.add_function("break", BREAK);
         k.add_function("continue", CONTINUE);
+        k.add_function("return", RETURN);
         k.add_function("dec", DEC);
         k.add_function("inc", INC);
         k.add_function("include", INCLUDE);
         k.add_function("let", LET);
         k.add_function("struct", STRUCT);
         k.add_function("get_field", GET_FIELD);
+        k.add_function("set_field", SET_FIELD);
+        k.add_function("import", IMPORT);
+        k.add_function("export", EXPORT);
+        k.add_function("==", EQ);
+        k.add_function("!=", NE);
+        k.add_function("<", LT);
+        k.add_function(">", GT);
+        k.add_function("<=", LE);
+        k.add_function(">=", GE);
+        k.add_function("&&", AND);
+        k.add_function("||", OR);
+        k.add_function("not", NOT);
+        k.add_function("match", MATCH);
+        k.add_function("array", ARRAY);
+        k.add_function("get", GET);
+        k.add_function("len", LEN);
+        k.add_function("for_each", FOR_EACH);
+        k.add_function("const", CONST);
+        k.add_function("lambda", LAMBDA);
+        k.add_function("test", TEST);
+        k.add_function("assert", ASSERT);
+        k.add_function("assert_eq", ASSERT_EQ);
+        k.add_function("print_str", PRINT_STR);
+        k.add_function("read_char", READ_CHAR);
+        k.add_function("read_digit", READ_DIGIT);
+        k.add_function("eof", EOF);
+        k.add_function("infix", INFIX);
+        for (name, precedence) in [
+            ("||", 2),
+            ("&&", 3),
+            ("==", 4),
+            ("!=", 4),
+            ("<", 4),
+            (">", 4),
+            ("<=", 4),
+            (">=", 4),
+        ] {
+            k.operators.insert(
+                name.to_owned(),
+                Operator::new(precedence, Associativity::Left),
+            );
+        }
         //k.add_function("if0", IF0);
         k
     }
@@ -53,26 +156,178 @@ impl ScopedState {
         handler: impl Fn(&mut State, &mut ScopedState, &FunctionCall) -> Result<Option<CVariable>>
             + 'static,
     ) {
-        self.functions
-            .insert(name.to_owned(), Rc::new(Box::new(handler)));
+        self.functions.insert(
+            name.to_owned(),
+            vec![Overload {
+                handler: Rc::new(Box::new(handler)),
+                arguments: None,
+            }],
+        );
+    }
+    /// Adds a definition to the overloads of the name. It replaces the definition with the same
+    /// number of arguments whatever their modes, the builtin or constructor with this name is kept
+    /// for the calls no definition takes.
+    pub fn add_overload(&mut self, name: &str, overload: Overload) {
+        let arity = overload.arguments.as_ref().map(|x| x.len());
+        let overloads = self.functions.entry(name.to_owned()).or_default();
+        overloads.retain(|x| x.arguments.as_ref().map(|x| x.len()) != arity);
+        overloads.push(overload);
+    }
+    pub fn add_overloads(&mut self, name: &str, overloads: Vec<Overload>) {
+        self.functions.insert(name.to_owned(), overloads);
+    }
+    pub fn get_operator(&self, name: &str) -> Operator {
+        self.operators.get(name).copied().unwrap_or_default()
+    }
+    pub fn get_function(&self, name: &str) -> Option<&Vec<Overload>> {
+        self.functions.get(name)
+    }
+    pub fn functions(&self) -> impl Iterator<Item = (&String, &Vec<Overload>)> {
+        self.functions.iter()
+    }
+    pub fn function_names(&self) -> impl Iterator<Item = &String> {
+        self.functions.keys()
     }
     pub fn execute(&mut self, call: &FunctionCall, state: &mut State) -> Result<Option<CVariable>> {
         self.call_graph.push(call.name.clone());
-        self.functions
-            .get(&call.name)
-            .ok_or_else(|| {
-                CError(
-                    vec![call.span.clone()],
-                    CErrorType::FunctionNotFound(call.name.to_string()),
-                )
-            })?
-            .clone()(state, self, call)
+        // A variable holding a function hides the functions with the same name
+        if let Some(overloads) = self.function_value(call, state) {
+            let spread = self.spread(&overloads, call);
+            let call = spread.as_ref().unwrap_or(call);
+            return resolve(&overloads, call)?(state, self, call);
+        }
+        if !self.functions.contains_key(&call.name) {
+            if let Some(e) = self.method_call(call, state)? {
+                return self.execute(&e, state);
+            }
+        }
+        let overloads = self.functions.get(&call.name).ok_or_else(|| {
+            CError(
+                vec![call.span.clone()],
+                CErrorType::FunctionNotFound(call.name.to_string()),
+            )
+        })?;
+        let spread = self.spread(overloads, call);
+        let call = spread.as_ref().unwrap_or(call);
+        resolve(overloads, call)?(state, self, call)
+    }
+
+    /// The definitions of the function stored in the variable or the field named like the call
+    fn function_value(&self, call: &FunctionCall, state: &mut State) -> Option<Vec<Overload>> {
+        let value = match self.variables.get(&call.name) {
+            Some(e) => Some(e.clone()),
+            // The errors of the path are reported by the method call
+            None => self
+                .get_field_path(&call.span, &call.name, state)
+                .ok()
+                .flatten(),
+        };
+        match value {
+            Some(CVariable::Function(_, e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// `f(...rest)` passes the elements of the list `rest` as separate arguments. Only the
+    /// functions defined with `fn`, `macro` or `lambda` are spread, builtins get the arguments
+    /// as written.
+    fn spread(&self, overloads: &[Overload], call: &FunctionCall) -> Option<FunctionCall> {
+        if overloads.iter().all(|x| x.arguments.is_none()) {
+            return None;
+        }
+        let list = |x: &Expression| match x {
+            Expression::Literal(span, name) => {
+                let name = name.strip_prefix("...")?;
+                match self.variables.get(name) {
+                    Some(CVariable::List(_, e)) => Some(
+                        (0..e.len())
+                            .map(|i| Expression::Literal(span.clone(), format!("{}.{}", name, i)))
+                            .collect::<Vec<_>>(),
+                    ),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if !call.arguments.iter().any(|x| list(x).is_some()) {
+            return None;
+        }
+        Some(FunctionCall {
+            arguments: call
+                .arguments
+                .iter()
+                .flat_map(|x| list(x).unwrap_or_else(|| vec![x.clone()]))
+                .collect(),
+            ..call.clone()
+        })
+    }
+
+    /// `a.b.method(args)` is `Struct.method(a.b, args)` where `Struct` is the name of the
+    /// struct in `a.b`.
+    fn method_call(&self, call: &FunctionCall, state: &mut State) -> Result<Option<FunctionCall>> {
+        let (receiver, method) = match call.name.rsplit_once('.') {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+        let value = match self.variables.get(receiver) {
+            Some(e) => Some(e.clone()),
+            None => self.get_field_path(&call.span, receiver, state)?,
+        };
+        Ok(match value {
+            Some(CVariable::Struct(_, b)) => {
+                let mut arguments =
+                    vec![Expression::Literal(call.span.clone(), receiver.to_owned())];
+                arguments.extend(call.arguments.iter().cloned());
+                Some(FunctionCall {
+                    name: format!("{}.{}", b.name, method),
+                    arguments,
+                    ..call.clone()
+                })
+            }
+            _ => None,
+        })
     }
     pub fn get_variable(&self, span: &[CSpan], name: &str) -> Result<&CVariable> {
         self.variables
             .get(name)
             .ok_or_else(|| CError(span.to_vec(), CErrorType::VariableNotFound(name.to_owned())))
     }
+    /// Resolves `a.b.c` to the field `c` of the field `b` of the struct `a`.
+    /// Returns `None` if the name isn't a path starting with a variable.
+    pub fn get_field_path(
+        &self,
+        span: &CSpan,
+        name: &str,
+        state: &mut State,
+    ) -> Result<Option<CVariable>> {
+        if self.variables.contains_key(name) {
+            return Ok(None);
+        }
+        let mut path = name.split('.');
+        let mut value = match path.next().and_then(|x| self.variables.get(x)) {
+            Some(e) if name.contains('.') => e.clone(),
+            _ => return Ok(None),
+        };
+        for field in path {
+            value = match value.unroll(state)? {
+                Some(CVariable::Struct(_, b)) => b.fields.get(field).cloned().ok_or_else(|| {
+                    CError(
+                        vec![span.clone()],
+                        CErrorType::FieldNotFound(field.to_owned(), b.name.clone()),
+                    )
+                })?,
+                // `rest.0` is the first element of a list
+                Some(CVariable::List(_, b)) => match field.parse::<usize>() {
+                    Ok(e) => b.get(e).cloned().ok_or_else(|| {
+                        CError(vec![span.clone()], CErrorType::IndexOutOfBounds(e, b.len()))
+                    })?,
+                    Err(_) => return Err(CError(vec![span.clone()], CErrorType::ExpectedStruct)),
+                },
+                _ => return Err(CError(vec![span.clone()], CErrorType::ExpectedStruct)),
+            };
+        }
+        Ok(Some(value))
+    }
     pub fn link_variable(&mut self, name: &str, pos: CVariable) {
         self.variables.insert(name.to_owned(), pos);
     }
@@ -104,3 +359,60 @@ impl ScopedState {
         self.variables.insert(name.to_owned(), variable);
     }
 }
+
+/// Selects the overload taking the number of arguments of the call. If there are several,
+/// the one with the most arguments passed in their preferred mode is used. The builtin or the
+/// constructor only gets the calls no definition takes.
+fn resolve(overloads: &[Overload], call: &FunctionCall) -> Result<Rc<Handler>> {
+    let mut errors = Vec::new();
+    let candidates: Vec<((bool, usize), &Overload)> = overloads
+        .iter()
+        .filter_map(|x| match &x.arguments {
+            Some(e) => match bind_arguments(e, call) {
+                Ok(bound) => Some((
+                    (
+                        true,
+                        e.iter()
+                            .zip(bound)
+                            .map(|(a, b)| b.iter().filter(|x| a.argument.prefers(x)).count())
+                            .sum(),
+                    ),
+                    x,
+                )),
+                Err(e) => {
+                    errors.push(e);
+                    None
+                }
+            },
+            None => Some(((false, 0), x)),
+        })
+        .collect();
+    let best = candidates.iter().map(|x| x.0).max();
+    let best: Vec<&Overload> = candidates
+        .iter()
+        .filter(|x| Some(x.0) == best)
+        .map(|x| x.1)
+        .collect();
+    let signatures = |x: &[&Overload]| x.iter().map(|x| x.signature(&call.name)).collect();
+    match best.as_slice() {
+        [e] => Ok(e.handler.clone()),
+        // With a single definition the error can name what is wrong with the call
+        [] if overloads.len() == 1
+            && !matches!(errors[0].1, CErrorType::WrongNumberOfArgument(_)) =>
+        {
+            Err(errors.remove(0))
+        }
+        [] => Err(CError(
+            vec![call.span.clone()],
+            CErrorType::NoMatchingOverload(
+                call.name.clone(),
+                call.arguments.len(),
+                signatures(&overloads.iter().collect::<Vec<_>>()),
+            ),
+        )),
+        e => Err(CError(
+            vec![call.span.clone()],
+            CErrorType::AmbiguousOverload(call.name.clone(), signatures(e)),
+        )),
+    }
+}
@@ -1,11 +1,31 @@
-use super::{
-    mir::MirCodeBlock,
-};
+use std::{collections::HashMap, path::PathBuf, rc::Rc};
+
+use super::{error::CSpan, mir::MirCodeBlock, scope::Overload};
+
+/// The functions exported by an imported file
+pub type Module = Rc<Vec<(String, Vec<Overload>)>>;
 
 pub struct State {
     counter: usize,
     pub base: u8,
     pub instructions: MirCodeBlock,
+    /// Already imported files, by canonical path
+    pub modules: HashMap<PathBuf, Module>,
+    /// Files being imported, used to detect circular imports
+    pub importing: Vec<PathBuf>,
+    /// Directories searched by `include` and `import`
+    pub include_paths: Vec<PathBuf>,
+    /// The test whose block is compiled, the other `test` blocks are skipped
+    pub test: Option<String>,
+    /// The names of the `test` blocks found so far
+    pub tests: Vec<(CSpan, String)>,
+    /// The position of each `assert`, the index is given to the test runner when it fails
+    pub asserts: Vec<CSpan>,
+    /// The ids of the loops whose body is being compiled, innermost last
+    pub loops: Vec<usize>,
+    /// The return cells of the functions whose body is being compiled, innermost last, and
+    /// whether a `return` leaves them
+    pub functions: Vec<(usize, bool)>,
 }
 
 impl Default for State {
@@ -14,6 +34,14 @@ impl Default for State {
             counter: 0,
             base: 4,
             instructions: MirCodeBlock(Vec::new()),
+            modules: HashMap::new(),
+            importing: Vec::new(),
+            include_paths: Vec::new(),
+            test: None,
+            tests: Vec::new(),
+            asserts: Vec::new(),
+            loops: Vec::new(),
+            functions: Vec::new(),
         }
     }
 }
@@ -23,4 +51,8 @@ impl State {
         self.counter += 1;
         self.counter
     }
+    /// The last allocated variable, the next ones are greater
+    pub fn last_count(&self) -> usize {
+        self.counter
+    }
 }
@@ -3,8 +3,9 @@ use std::collections::HashMap;
 use super::{
     asm::{AsmValue, Number, Var},
     error::{CError, CErrorType, CSpan},
+    mir::Mir,
     parser::expression::Expression,
-    scope::ScopedState,
+    scope::{Overload, ScopedState},
     state::State,
 };
 
@@ -15,6 +16,12 @@ pub enum CVariable {
     Value(Vec<CSpan>, usize),
     Number(Vec<CSpan>, u8),
     Struct(Vec<CSpan>, StructRef),
+    /// The cells of the elements
+    Array(Vec<CSpan>, Vec<usize>),
+    /// The arguments bound to a variadic parameter, only exists at compile time
+    List(Vec<CSpan>, Vec<CVariable>),
+    /// A function used as a value, calling the variable calls one of the definitions
+    Function(Vec<CSpan>, Vec<Overload>),
     ExpressionRef(Vec<CSpan>, Box<Expression>, ScopedState),
 }
 
@@ -25,6 +32,15 @@ pub struct StructRef {
     pub fields: HashMap<String, CVariable>,
 }
 
+impl StructRef {
+    /// The fields ordered by name so the generated code doesn't depend on the map order
+    pub fn sorted_fields(&self) -> Vec<(&String, &CVariable)> {
+        let mut fields: Vec<_> = self.fields.iter().collect();
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+        fields
+    }
+}
+
 impl CVariable {
     pub fn unroll(&self, state: &mut State) -> Result<Option<Self>> {
         Ok(match self {
@@ -53,6 +69,18 @@ impl CVariable {
                 a.insert(0, span);
                 CVariable::Struct(a, b)
             }
+            CVariable::Array(mut a, b) => {
+                a.insert(0, span);
+                CVariable::Array(a, b)
+            }
+            CVariable::List(mut a, b) => {
+                a.insert(0, span);
+                CVariable::List(a, b)
+            }
+            CVariable::Function(mut a, b) => {
+                a.insert(0, span);
+                CVariable::Function(a, b)
+            }
         }
     }
     pub fn to_asm(&self, state: &mut State) -> Result<AsmValue> {
@@ -64,6 +92,18 @@ impl CVariable {
                 a.clone(),
                 CErrorType::StructUsedAsVariableInInvalidContext(b.name.to_owned()),
             )),
+            CVariable::Array(a, _) => Err(CError(
+                a.clone(),
+                CErrorType::ArrayUsedAsVariableInInvalidContext,
+            )),
+            CVariable::List(a, _) => Err(CError(
+                a.clone(),
+                CErrorType::ListUsedAsVariableInInvalidContext,
+            )),
+            CVariable::Function(a, _) => Err(CError(
+                a.clone(),
+                CErrorType::FunctionUsedAsVariableInInvalidContext,
+            )),
         }
     }
 
@@ -73,12 +113,107 @@ impl CVariable {
             .ok_or_else(|| CError(self.get_span().to_vec(), CErrorType::ExpectedVariable))
     }
 
+    /// Copies the value in fresh cells, including the fields of structs and the elements of
+    /// arrays. `$` fields aren't computed and stay lazy in the copy.
+    pub fn deep_copy(&self, state: &mut State) -> Result<Self> {
+        Ok(match self {
+            Self::Value(a, _) | Self::Number(a, _) => {
+                let k = state.count();
+                let tmp = self.to_asm(state)?;
+                state.instructions.push(Mir::Copy(Var(k), tmp));
+                Self::Value(a.clone(), k)
+            }
+            Self::Struct(a, b) => {
+                let mut fields = HashMap::new();
+                for (name, field) in b.sorted_fields() {
+                    fields.insert(name.clone(), field.deep_copy(state)?);
+                }
+                Self::Struct(
+                    a.clone(),
+                    StructRef {
+                        fields,
+                        ..b.clone()
+                    },
+                )
+            }
+            Self::Array(a, b) => Self::Array(
+                a.clone(),
+                b.iter()
+                    .map(|x| {
+                        let k = state.count();
+                        state
+                            .instructions
+                            .push(Mir::Copy(Var(k), AsmValue::Var(Var(*x))));
+                        k
+                    })
+                    .collect(),
+            ),
+            Self::List(a, b) => Self::List(
+                a.clone(),
+                b.iter()
+                    .map(|x| x.deep_copy(state))
+                    .collect::<Result<_>>()?,
+            ),
+            Self::ExpressionRef(..) | Self::Function(..) => self.clone(),
+        })
+    }
+
+    /// Copies the value in the cells of this variable, structs and arrays are copied element by
+    /// element so every name referencing them sees the change.
+    pub fn assign(&self, value: &CVariable, state: &mut State) -> Result<()> {
+        match (self, value) {
+            (Self::Struct(_, a), Self::Struct(_, b)) if a.name == b.name => {
+                for (name, field) in a.sorted_fields() {
+                    // Two structs declared in different scopes can have the same name
+                    let from = b.fields.get(name).ok_or_else(|| {
+                        CError(
+                            value.get_span().to_vec(),
+                            CErrorType::IncompatibleAssignment,
+                        )
+                    })?;
+                    field.assign(from, state)?;
+                }
+            }
+            (Self::Array(_, a), Self::Array(_, b)) if a.len() == b.len() => {
+                for (x, y) in a.iter().zip(b) {
+                    state
+                        .instructions
+                        .push(Mir::Copy(Var(*x), AsmValue::Var(Var(*y))));
+                }
+            }
+            (Self::List(_, a), Self::List(_, b)) if a.len() == b.len() => {
+                for (x, y) in a.iter().zip(b) {
+                    x.assign(y, state)?;
+                }
+            }
+            (Self::ExpressionRef(a, ..), _) => {
+                return Err(CError(a.clone(), CErrorType::ComputedFieldAssignment))
+            }
+            (Self::Struct(..) | Self::Array(..) | Self::List(..) | Self::Function(..), _)
+            | (_, Self::Struct(..) | Self::Array(..) | Self::List(..) | Self::Function(..)) => {
+                return Err(CError(
+                    value.get_span().to_vec(),
+                    CErrorType::IncompatibleAssignment,
+                ))
+            }
+            _ => {
+                let to = self.as_var(state)?;
+                let from = value.to_asm(state)?;
+                state.instructions.push(Mir::Copy(to, from));
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_span(&self) -> &[CSpan] {
         match self {
             Self::Value(a, _)
             | Self::Number(a, _)
             | Self::ExpressionRef(a, ..)
-            | Self::Struct(a, ..) => a,
+            | Self::Struct(a, ..)
+            | Self::Array(a, ..)
+            | Self::List(a, ..)
+            | Self::Function(a, ..) => a,
         }
     }
 
@@ -90,3 +225,14 @@ impl CVariable {
         }
     }
 }
+
+/// A number known when compiling which is used as a value, so it has to fit in a cell
+pub fn cell_number(state: &State, span: &CSpan, value: usize) -> Result<CVariable> {
+    if value >= 1 << state.base {
+        return Err(CError(
+            vec![span.clone()],
+            CErrorType::ValueDoesntFit(value, state.base),
+        ));
+    }
+    Ok(CVariable::Number(vec![span.clone()], value as u8))
+}
@@ -0,0 +1,168 @@
+//! Canonical formatting of CH2L sources:
+//! - statements are indented by 4 spaces and always end with `;`
+//! - blocks containing a single expression use the `:expr` form, the others `{...}`
+//! - arguments are separated by `, `, named arguments written `name: value` and operators
+//!   surrounded by single spaces
+//! - comments are kept, as are single blank lines between statements
+
+use crate::compiler::{
+    parser::cst::{Cst, CstKind, CstNode},
+    type_defs::Result,
+};
+
+const INDENT: &str = "    ";
+
+pub fn format_source(file_name: &str, source: String) -> Result<String> {
+    let cst = Cst::parse(file_name, source, vec![])?;
+    debug_assert_eq!(cst.to_source(), cst.source);
+    let mut out = String::new();
+    Printer { cst: &cst }.statements(&cst.root, 0, &mut out);
+    Ok(out)
+}
+
+struct Printer<'a> {
+    cst: &'a Cst,
+}
+
+impl<'a> Printer<'a> {
+    fn text(&self, node: &CstNode) -> &'a str {
+        self.cst.text(node).trim()
+    }
+
+    /// Writes the children of a file or a block, one statement per line.
+    fn statements(&self, parent: &CstNode, indent: usize, out: &mut String) {
+        let mut last_end = None;
+        for i in &parent.children {
+            let gap = &self.cst.source[last_end.unwrap_or(parent.start)..i.start];
+            let newlines = gap.matches('\n').count();
+            if i.kind == CstKind::Comment && last_end.is_some() && newlines == 0 {
+                // Comment on the same line as the previous statement
+                out.pop();
+                out.push(' ');
+                out.push_str(self.text(i));
+                out.push('\n');
+            } else {
+                if newlines > 1 && last_end.is_some() {
+                    out.push('\n');
+                }
+                out.push_str(&INDENT.repeat(indent));
+                if i.kind == CstKind::Comment {
+                    out.push_str(self.text(i));
+                } else {
+                    out.push_str(&self.expression(i, indent, false));
+                    out.push(';');
+                }
+                out.push('\n');
+            }
+            last_end = Some(i.end);
+        }
+    }
+
+    /// Joins the children with spaces, comments stay next to the node they follow.
+    fn spaced(&self, node: &CstNode, indent: usize, first_colon: bool) -> String {
+        node.children
+            .iter()
+            .enumerate()
+            .map(|(k, x)| self.expression(x, indent, k != 0 || first_colon))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `colon` is false when a `:expr` block would swallow what follows it.
+    fn expression(&self, node: &CstNode, indent: usize, colon: bool) -> String {
+        match node.kind {
+            CstKind::Literal
+            | CstKind::Number
+            | CstKind::String
+            | CstKind::Comment
+            | CstKind::File => self.text(node).to_owned(),
+            CstKind::Operation => self.spaced(node, indent, false),
+            CstKind::UnaryOperation => self.spaced(node, indent, true),
+            CstKind::FunctionCall => {
+                let mut args: Vec<String> = Vec::new();
+                // Comments before the first argument are written in front of it
+                let mut leading: Vec<String> = Vec::new();
+                for i in node.children.iter().skip(1) {
+                    let e = self.expression(i, indent, true);
+                    match (i.kind, args.last_mut()) {
+                        (CstKind::Comment, Some(last)) => {
+                            last.push(' ');
+                            last.push_str(&e);
+                        }
+                        (CstKind::Comment, None) => leading.push(e),
+                        (_, None) if !leading.is_empty() => {
+                            args.push(format!("{} {}", leading.join(" "), e))
+                        }
+                        _ => args.push(e),
+                    }
+                }
+                if args.is_empty() && !leading.is_empty() {
+                    args.push(leading.join(" "));
+                }
+                // `f(((c)) a)` would read the comment as an expression in parentheses
+                let space = if leading.is_empty() { "" } else { " " };
+                format!(
+                    "{}({}{})",
+                    self.text(&node.children[0]),
+                    space,
+                    args.join(", ")
+                )
+            }
+            CstKind::NamedArgument => format!(
+                "{}: {}",
+                self.text(&node.children[0]),
+                self.expression(node.nodes().last().unwrap(), indent, true)
+            ),
+            CstKind::CodeBlock(_) => {
+                let has_comments = node.children.len() != node.nodes().count();
+                let mut nodes = node.nodes();
+                match (nodes.next(), nodes.next(), has_comments) {
+                    (None, _, false) => "{}".to_owned(),
+                    (Some(e), None, false) if colon => {
+                        format!(":{}", self.expression(e, indent, true))
+                    }
+                    _ => {
+                        let mut out = String::from("{\n");
+                        self.statements(node, indent + 1, &mut out);
+                        out.push_str(&INDENT.repeat(indent));
+                        out.push('}');
+                        out
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Formats the files in place, or only reports the unformatted ones with `check`.
+/// Returns the process exit code.
+pub fn run(files: &[String], check: bool) -> i32 {
+    let mut code = 0;
+    for i in files {
+        let source = match std::fs::read_to_string(i) {
+            Ok(e) => e,
+            Err(_) => {
+                println!("Can't read `{}` file. Ensure that the path is correct", i);
+                return -1;
+            }
+        };
+        let formatted = match format_source(i, source.clone()) {
+            Ok(e) => e,
+            Err(e) => {
+                println!("{}", e);
+                return -1;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{} isn't formatted", i);
+            code = 1;
+        } else if std::fs::write(i, formatted).is_err() {
+            println!("Can't write `{}` file", i);
+            return -1;
+        }
+    }
+    code
+}
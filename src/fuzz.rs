@@ -0,0 +1,285 @@
+//! `cyc fuzz` generates random well-formed programs and compiles each of them through the whole
+//! pipeline. A program fails when the compiler panics, when the CythanV3 compiler rejects the
+//! generated code, or when the optimised MIR or the compiled code don't behave like the MIR.
+
+use std::{
+    cell::RefCell,
+    panic::{catch_unwind, AssertUnwindSafe},
+    rc::Rc,
+};
+
+use cythan::{Cythan, InterruptedCythan};
+
+use crate::{
+    compile,
+    compiler::{
+        error::CErrorType,
+        mir::{
+            interpreter::{EvalError, Interpreter, Io},
+            optimizer, Mir, MirCodeBlock,
+        },
+        scope::ScopedState,
+        state::State,
+    },
+    execute_source,
+    template::get_interrupt_pos_from_base,
+};
+
+/// Programs running longer are considered to never stop and aren't compared
+pub const MAX_STEPS: usize = 10_000_000;
+
+/// The standard library only exists in base 4
+const BASE: u8 = 4;
+
+const VARIABLES: usize = 4;
+const MAX_DEPTH: usize = 3;
+
+/// The output and the exit code of a program, `None` if it doesn't stop
+pub type Run = Option<(String, u8)>;
+
+pub fn run(runs: usize, seed: u64) -> i32 {
+    for i in 0..runs as u64 {
+        let seed = seed.wrapping_add(i);
+        let program = generate(seed);
+        if let Err(e) = check(&program) {
+            println!("The program {} failed: {}", seed, e);
+            println!("{}", program);
+            println!("Run `cyc fuzz 1 {}` to reproduce it", seed);
+            return 1;
+        }
+    }
+    println!("{} programs passed", runs);
+    0
+}
+
+/// Compiles and runs the program, the error describes why it failed
+pub fn check(program: &str) -> Result<(), String> {
+    match catch_unwind(AssertUnwindSafe(|| check_program(program))) {
+        Ok(e) => e,
+        Err(e) => Err(format!(
+            "the compiler panicked: {}",
+            e.downcast_ref::<&str>()
+                .map(|x| x.to_string())
+                .or_else(|| e.downcast_ref::<String>().cloned())
+                .unwrap_or_default()
+        )),
+    }
+}
+
+fn check_program(program: &str) -> Result<(), String> {
+    let mut state = State::default();
+    state.base = BASE;
+    execute_source(
+        "fuzz.ct1",
+        program.to_owned(),
+        &mut state,
+        &mut ScopedState::new(),
+        vec![],
+    )
+    .map_err(|e| format!("the program doesn't compile\n{}", e))?;
+    let reference = match interpret(state.instructions.0.clone(), state.base, "") {
+        Some(e) => e,
+        None => return Ok(()),
+    };
+    let optimized = interpret(
+        optimizer::opt(state.instructions.0.clone(), state.base),
+        state.base,
+        "",
+    );
+    if optimized != Some(reference.clone()) {
+        return Err(format!(
+            "the optimizer changed the behaviour, {:?} instead of {:?}",
+            optimized, reference
+        ));
+    }
+    let compiled = match run_compiled(&state, "") {
+        Ok(e) => e,
+        Err(CErrorType::InternalCompilerError(e)) => {
+            return Err(format!("the CythanV3 compiler failed: {}", e))
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+    if matches!(&compiled, Some(e) if *e != reference) {
+        return Err(format!(
+            "the compiled code behaves differently, {:?} instead of {:?}",
+            compiled, reference
+        ));
+    }
+    Ok(())
+}
+
+/// Runs the MIR of a whole program with the input
+pub fn interpret(instructions: Vec<Mir>, base: u8, input: &str) -> Run {
+    let mut interpreter = Interpreter::with_io(base, MAX_STEPS, Io::new(input.as_bytes().to_vec()));
+    match interpreter.run(&MirCodeBlock(instructions)) {
+        Ok(()) | Err(EvalError::Stopped) => (),
+        Err(EvalError::OutOfFuel) => return None,
+        Err(e) => panic!("{}", e),
+    }
+    let io = interpreter.io.unwrap();
+    Some((
+        io.output.iter().map(|x| *x as char).collect(),
+        io.exit_code(),
+    ))
+}
+
+/// Compiles the program and runs it on `InterruptedCythan` with the input
+pub fn run_compiled(state: &State, input: &str) -> Result<Run, CErrorType> {
+    let code = compile(state).map_err(|e| e.1)?;
+    let interrupt = get_interrupt_pos_from_base(state.base);
+    let output = Rc::new(RefCell::new(String::new()));
+    let printed = output.clone();
+    let input = RefCell::new(input.as_bytes().to_vec().into_iter());
+    let mut machine = InterruptedCythan::new(
+        code,
+        state.base,
+        interrupt,
+        move |a| printed.borrow_mut().push(a as char),
+        move || input.borrow_mut().next(),
+    );
+    for _ in 0..MAX_STEPS / 1000 {
+        for _ in 0..1000 {
+            machine.next();
+        }
+        let o = machine.cases.clone();
+        machine.next();
+        if o == machine.cases {
+            let exit_code = machine.get_value(interrupt) as u8;
+            return Ok(Some((output.borrow().clone(), exit_code)));
+        }
+    }
+    Ok(None)
+}
+
+/// A xorshift generator, the programs only have to be reproducible from their seed
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // The state can't be 0
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+/// Generates a program using the variables `v0` to `v3` which are all set at the start, the loops
+/// count down a counter of their own so every program stops.
+pub fn generate(seed: u64) -> String {
+    let mut generator = Generator {
+        rng: Rng::new(seed),
+        names: 0,
+        loops: Vec::new(),
+    };
+    let mut program = String::from("include(std);\n");
+    for i in 0..VARIABLES {
+        let value = generator.number();
+        program.push_str(&format!("v{} = {};\n", i, value));
+    }
+    for _ in 0..1 + generator.rng.below(8) {
+        program.push_str(&generator.statement(0));
+        program.push('\n');
+    }
+    program
+}
+
+struct Generator {
+    rng: Rng,
+    /// The number of counters and constants, to give each one a name of its own
+    names: usize,
+    /// The labels of the loops around the statement
+    loops: Vec<String>,
+}
+
+impl Generator {
+    fn number(&mut self) -> usize {
+        self.rng.below(1 << BASE)
+    }
+
+    fn variable(&mut self) -> String {
+        format!("v{}", self.rng.below(VARIABLES))
+    }
+
+    fn value(&mut self) -> String {
+        if self.rng.below(3) == 0 {
+            self.number().to_string()
+        } else {
+            self.variable()
+        }
+    }
+
+    fn expression(&mut self) -> String {
+        match self.rng.below(7) {
+            0 => format!("{} + {}", self.value(), self.value()),
+            1 => format!("{} - {}", self.value(), self.value()),
+            2 => format!("{} * {}", self.value(), self.value()),
+            3 => format!("{} == {}", self.value(), self.value()),
+            4 => format!("{} < {}", self.value(), self.value()),
+            _ => self.value(),
+        }
+    }
+
+    fn block(&mut self, depth: usize) -> String {
+        let statements = (0..1 + self.rng.below(3))
+            .map(|_| self.statement(depth + 1))
+            .collect::<Vec<_>>();
+        format!("{{ {} }}", statements.join(" "))
+    }
+
+    fn statement(&mut self, depth: usize) -> String {
+        let kinds = if depth < MAX_DEPTH { 9 } else { 6 };
+        match self.rng.below(kinds) {
+            0 | 1 => format!("{} = {};", self.variable(), self.expression()),
+            2 => format!("inc({});", self.variable()),
+            3 => format!("dec({});", self.variable()),
+            4 => format!("print(3, {});", self.variable()),
+            // The counters are decreased at the start of the loops, so `continue` ends too
+            5 if !self.loops.is_empty() => {
+                let label = self.loops[self.rng.below(self.loops.len())].clone();
+                let exit = if self.rng.below(2) == 0 {
+                    "break"
+                } else {
+                    "continue"
+                };
+                format!("if0({}, :{}({}));", self.variable(), exit, label)
+            }
+            5 => format!("inc({});", self.variable()),
+            6 => format!(
+                "if0({}, {}, {});",
+                self.variable(),
+                self.block(depth),
+                self.block(depth)
+            ),
+            7 => {
+                let counter = format!("c{}", self.names);
+                let label = format!("l{}", self.names);
+                self.names += 1;
+                let start = self.rng.below(4);
+                self.loops.push(label.clone());
+                let body = self.block(depth);
+                self.loops.pop();
+                format!(
+                    "{} = {}; loop({}, {{ if0({}, :break()); dec({}); {} }});",
+                    counter, start, label, counter, counter, body
+                )
+            }
+            _ => {
+                let name = format!("k{}", self.names);
+                self.names += 1;
+                format!(
+                    "const({}, {} + {}); {} = {};",
+                    name,
+                    self.number(),
+                    self.number(),
+                    self.variable(),
+                    name
+                )
+            }
+        }
+    }
+}
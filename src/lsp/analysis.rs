@@ -0,0 +1,555 @@
+use std::collections::{HashMap, HashSet};
+
+use pest::{error::LineColLocation, Position};
+
+use crate::{
+    compiler::{
+        error::{CError, CSpan},
+        parser::{expression::Expression, function_call::FunctionCall, parse_file},
+        resolver::{env_paths, read_source, resolve_file},
+        scope::ScopedState,
+        state::State,
+    },
+    execute_source,
+};
+
+/// Signatures of the ICL functions, shown on hover and in completions.
+pub const BUILTINS: &[(&str, &str, &str)] = &[
+    ("exit", "exit(<number>)", "Exits the program with the given code"),
+    (
+        "set_reg",
+        "set_reg(<number>, <value>)",
+        "Set the value of a register from a variable",
+    ),
+    (
+        "get_reg",
+        "get_reg(<&*variable>, <number>)",
+        "Push the value of a register to a variable",
+    ),
+    (
+        "if0",
+        "if0(<value>, <block if true>, <OPTIONAL: block if false>)",
+        "Will execute the first block if the value is 0 or else the second one if it exists",
+    ),
+    (
+        "include",
+        "include(<file to load>)",
+        "Will load the following file in scope",
+    ),
+    (
+        "import",
+        "import(<file to load>, as: <name>)",
+        "Will load the file once in its own scope and make its exported functions available as `name.function`",
+    ),
+    (
+        "export",
+        "export(<functions...>)",
+        "Will restrict the functions available to the files importing this one",
+    ),
+    (
+        "set",
+        "set(<&*variable>, <value>) | set(<array>, <index>, <value>)",
+        "Will set or [create a variable if it doesn't exists in scope], or set the element of an array",
+    ),
+    (
+        "=",
+        "<&*variable> = <value>",
+        "Will set or [create a variable if it doesn't exists in scope]",
+    ),
+    (
+        "lambda",
+        "lambda(<arguments...>, <code block>)",
+        "Will return a function without a name that can be stored and passed around",
+    ),
+    (
+        "const",
+        "const(<name>, <value>)",
+        "Will compute the value when compiling and create a constant with it",
+    ),
+    (
+        "let",
+        "let(<&*variable>, <value>)",
+        "Will [set in scope only] or [create a variable if it doesn't exists in scope]",
+    ),
+    (
+        "fn",
+        "fn(<name>, <arguments...>, <code block>)",
+        "Will create a function in the scope",
+    ),
+    (
+        "macro",
+        "macro(<name>, <arguments...>, <code block>)",
+        "Will create a function whose body only sees its arguments, the variables it uses are fresh for each call",
+    ),
+    ("inc", "inc(<&variable>)", "Will increment the variable ref"),
+    ("dec", "dec(<&variable>)", "Will decrement the variable ref"),
+    (
+        "loop",
+        "loop(<OPTIONAL: label>, <code block>)",
+        "Will execute the code block until break() is called, will restart the execution when continue() is called",
+    ),
+    (
+        "continue",
+        "continue(<OPTIONAL: label>)",
+        "Will restart the current loop or the loop with the label",
+    ),
+    (
+        "break",
+        "break(<OPTIONAL: label>)",
+        "Will exit the current loop or the loop with the label",
+    ),
+    ("return", "return(<value>)", "Will exit the function with the value"),
+    (
+        "==",
+        "<value> == <value>",
+        "0 if the values are equal, 1 otherwise",
+    ),
+    ("!=", "<value> != <value>", "0 if the values are different, 1 otherwise"),
+    ("<", "<value> < <value>", "0 if the first value is lower, 1 otherwise"),
+    (">", "<value> > <value>", "0 if the first value is greater, 1 otherwise"),
+    (
+        "<=",
+        "<value> <= <value>",
+        "0 if the first value is lower or equal, 1 otherwise",
+    ),
+    (
+        ">=",
+        "<value> >= <value>",
+        "0 if the first value is greater or equal, 1 otherwise",
+    ),
+    ("&&", "<value> && <value>", "0 if both values are 0, 1 otherwise"),
+    ("||", "<value> || <value>", "0 if one of the values is 0, 1 otherwise"),
+    ("not", "not(<value>)", "0 if the value isn't 0, 1 otherwise"),
+    (
+        "infix",
+        "infix(<operator>, <precedence>, <left|right>)",
+        "Will set the precedence and the associativity of the operator in the scope",
+    ),
+    (
+        "match",
+        "match(<value>, <number>: <case>..., _: <OPTIONAL: default case>)",
+        "Will execute the case of the value or else the `_` case if it exists, using a single jump",
+    ),
+    (
+        "array",
+        "array(<number>)",
+        "Will create an array of the given length, filled with 0",
+    ),
+    (
+        "get",
+        "get(<array>, <index>)",
+        "Will return the element at the index, the program stops if the index is out of bounds",
+    ),
+    (
+        "len",
+        "len(<array or list>)",
+        "Will return the number of elements of the array or list",
+    ),
+    (
+        "for_each",
+        "for_each(<name>, <list or array>, <code block>)",
+        "Will expand the code block for each element, bound to the name",
+    ),
+    (
+        "print_str",
+        "print_str(<string or character>...)",
+        "Will print the strings and characters",
+    ),
+    (
+        "read_char",
+        "read_char()",
+        "Will read a character of the input, 0 once the input is over",
+    ),
+    (
+        "read_digit",
+        "read_digit()",
+        "Will read a character of the input and return its digit, the greatest value of a cell if it isn't one",
+    ),
+    (
+        "eof",
+        "eof()",
+        "Will return 0 if the last read reached the end of the input, 1 if it read a character",
+    ),
+    (
+        "test",
+        "test(<name>, <code block>)",
+        "Will declare a test, only compiled by `cyc test`",
+    ),
+    (
+        "assert",
+        "assert(<value>)",
+        "Will stop the program if the value isn't 0",
+    ),
+    (
+        "assert_eq",
+        "assert_eq(<value>, <value>)",
+        "Will stop the program if the values are different",
+    ),
+    (
+        "struct",
+        "struct(<name>, <fields...>)",
+        "Will create a struct constructor in the scope, fields can be prefixed by `&`, `&*` or `$` like function arguments",
+    ),
+    (
+        "get_field",
+        "get_field(<struct>, <field>)",
+        "Will return the field of a struct",
+    ),
+    (
+        "set_field",
+        "set_field(<struct>, <field>, <value>)",
+        "Will set the field of a struct in place",
+    ),
+];
+
+/// A zero based `(line, column)` range as used by the protocol, the columns are counted in
+/// UTF-16 code units.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Range {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl Range {
+    fn from_span(span: &CSpan) -> Self {
+        Self {
+            start: position(&span.span.start_pos()),
+            end: position(&span.span.end_pos()),
+        }
+    }
+
+    /// The range covering the start of the span, `len` is in UTF-16 code units.
+    fn from_span_prefix(span: &CSpan, len: usize) -> Self {
+        let start = position(&span.span.start_pos());
+        Self {
+            start,
+            end: (start.0, start.1 + len),
+        }
+    }
+
+    pub fn contains(&self, pos: (usize, usize)) -> bool {
+        self.start <= pos && pos <= self.end
+    }
+}
+
+/// The protocol position of a pest position.
+fn position(position: &Position) -> (usize, usize) {
+    let (line, column) = position.line_col();
+    (line - 1, utf16_column(position.line_of(), column))
+}
+
+/// Converts a one based column counted in characters, like pest gives them, to a zero based column
+/// counted in UTF-16 code units.
+pub fn utf16_column(line: &str, column: usize) -> usize {
+    line.chars()
+        .take(column.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum()
+}
+
+pub struct Diagnostic {
+    pub range: Range,
+    pub message: String,
+}
+
+pub enum DefinitionKind {
+    Function(Vec<String>),
+    Struct(Vec<String>),
+}
+
+pub struct Definition {
+    pub name: String,
+    pub kind: DefinitionKind,
+    pub file: String,
+    pub range: Range,
+}
+
+impl Definition {
+    pub fn signature(&self) -> String {
+        match &self.kind {
+            DefinitionKind::Function(a) => format!("fn({})", with_name(&self.name, a)),
+            DefinitionKind::Struct(a) => format!("struct({})", with_name(&self.name, a)),
+        }
+    }
+
+    pub fn documentation(&self) -> String {
+        let mut out = format!("```\n{}\n```", self.signature());
+        if let DefinitionKind::Function(a) = &self.kind {
+            for arg in a {
+                out.push_str(&format!("\n- `{}` {}", arg, argument_mode(arg)));
+            }
+        }
+        out
+    }
+}
+
+fn with_name(name: &str, args: &[String]) -> String {
+    std::iter::once(name)
+        .chain(args.iter().map(|x| x.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn argument_mode(argument: &str) -> &'static str {
+    if argument.starts_with("&*") {
+        "reference, created in the caller's scope if it doesn't exist"
+    } else if argument.starts_with('&') {
+        "reference, modifications are visible to the caller"
+    } else if argument.starts_with('$') {
+        "lazy expression, evaluated in the caller's scope each time it is used"
+    } else {
+        "copy of the input value"
+    }
+}
+
+pub enum Reference {
+    Call(String, Range),
+    Include(String, Range),
+}
+
+impl Reference {
+    fn range(&self) -> &Range {
+        match self {
+            Reference::Call(_, a) | Reference::Include(_, a) => a,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub definitions: Vec<Definition>,
+    pub references: Vec<Reference>,
+    pub functions: Vec<String>,
+}
+
+impl Analysis {
+    /// Parses the document and the files it includes to find definitions, then runs the
+    /// compiler front-end on it to get the errors and the functions in scope.
+    pub fn new(file_name: &str, content: &str, documents: &HashMap<String, String>) -> Self {
+        let mut analysis = Self::default();
+        let mut state = State::default();
+        state.include_paths = env_paths();
+        analysis.collect(
+            &state,
+            file_name,
+            content.to_owned(),
+            documents,
+            &mut HashSet::new(),
+            "",
+        );
+
+        let mut scope = ScopedState::new();
+        if let Err(e) = execute_source(
+            file_name,
+            content.to_owned(),
+            &mut state,
+            &mut scope,
+            vec![],
+        ) {
+            analysis
+                .diagnostics
+                .push(diagnostic(file_name, content, &e));
+        }
+        analysis.functions = scope.function_names().cloned().collect();
+        analysis
+    }
+
+    fn collect(
+        &mut self,
+        state: &State,
+        file_name: &str,
+        content: String,
+        documents: &HashMap<String, String>,
+        visited: &mut HashSet<String>,
+        prefix: &str,
+    ) {
+        let root = visited.is_empty();
+        if !visited.insert(file_name.to_owned()) {
+            return;
+        }
+        let expressions = match parse_file(file_name, content, vec![]) {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+        let mut includes = Vec::new();
+        for e in &expressions {
+            self.walk(state, e, root, prefix, &mut includes);
+        }
+        for (i, prefix) in includes {
+            let content = match documents.get(&i) {
+                Some(e) => e.clone(),
+                None => match read_source(&i) {
+                    Some(e) => e,
+                    None => continue,
+                },
+            };
+            self.collect(state, &i, content, documents, visited, &prefix);
+        }
+    }
+
+    /// `prefix` qualifies the definitions of imported files, the included files are
+    /// returned with the prefix their definitions get.
+    fn walk(
+        &mut self,
+        state: &State,
+        expression: &Expression,
+        root: bool,
+        prefix: &str,
+        includes: &mut Vec<(String, String)>,
+    ) {
+        match expression {
+            Expression::FunctionCall(_, fc) => {
+                match fc.name.as_str() {
+                    "fn" | "macro" | "struct" => {
+                        if let Some(Expression::Literal(span, name)) = fc.arguments.first() {
+                            let end = if fc.name != "struct" {
+                                fc.arguments.len().saturating_sub(1).max(1)
+                            } else {
+                                fc.arguments.len()
+                            };
+                            // Parameters with a default value are shown as written
+                            let args = fc.arguments[1..end]
+                                .iter()
+                                .map(|x| match x.get_literal() {
+                                    Ok((_, e)) => e.clone(),
+                                    Err(_) => x.get_span().span.as_str().to_owned(),
+                                })
+                                .collect();
+                            self.definitions.push(Definition {
+                                name: format!("{}{}", prefix, name),
+                                kind: if fc.name != "struct" {
+                                    DefinitionKind::Function(args)
+                                } else {
+                                    DefinitionKind::Struct(args)
+                                },
+                                file: span.get_filename().to_owned(),
+                                range: Range::from_span(span),
+                            });
+                        }
+                    }
+                    "include" | "import" => {
+                        let target = match fc.arguments.first() {
+                            Some(Expression::Literal(span, path)) => {
+                                resolve_file(state, &fc.span, path).map(|x| (span, x))
+                            }
+                            _ => None,
+                        };
+                        if let Some((span, file)) = target {
+                            let prefix = if fc.name == "include" {
+                                prefix.to_owned()
+                            } else {
+                                match fc.get_named_argument("as").map(|x| &x.value) {
+                                    Some(Expression::Literal(_, e)) => format!("{}.", e),
+                                    _ => format!(
+                                        "{}.",
+                                        file.file_stem().unwrap_or_default().to_string_lossy()
+                                    ),
+                                }
+                            };
+                            let file = file.to_string_lossy().into_owned();
+                            if root {
+                                self.references
+                                    .push(Reference::Include(file.clone(), Range::from_span(span)));
+                            }
+                            // Imports of imported files aren't re-exported
+                            if fc.name == "include" || root {
+                                includes.push((file, prefix));
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+                if root && is_named_call(fc) {
+                    self.references.push(Reference::Call(
+                        fc.name.clone(),
+                        Range::from_span_prefix(&fc.span, fc.name.encode_utf16().count()),
+                    ));
+                }
+                for a in fc
+                    .arguments
+                    .iter()
+                    .chain(fc.named_arguments.iter().map(|x| &x.value))
+                {
+                    self.walk(state, a, root, prefix, includes);
+                }
+            }
+            Expression::CodeBlock(_, cb) => {
+                for a in &cb.0 {
+                    self.walk(state, a, root, prefix, includes);
+                }
+            }
+            Expression::Operation(_, operands, _) => {
+                for a in operands {
+                    self.walk(state, a, root, prefix, includes);
+                }
+            }
+            Expression::Literal(..)
+            | Expression::Number(..)
+            | Expression::Character(..)
+            | Expression::String(..) => (),
+        }
+    }
+
+    pub fn reference_at(&self, pos: (usize, usize)) -> Option<&Reference> {
+        self.references.iter().find(|x| x.range().contains(pos))
+    }
+
+    pub fn definition(&self, name: &str) -> Option<&Definition> {
+        self.definitions.iter().rev().find(|x| x.name == name)
+    }
+
+    pub fn hover(&self, pos: (usize, usize)) -> Option<String> {
+        match self.reference_at(pos)? {
+            Reference::Call(name, _) => {
+                if let Some(e) = self.definition(name) {
+                    return Some(e.documentation());
+                }
+                builtin(name).map(|(_, a, b)| format!("```\n{}\n```\n{}", a, b))
+            }
+            Reference::Include(file, _) => Some(format!("`{}`", file)),
+        }
+    }
+}
+
+pub fn builtin(name: &str) -> Option<&'static (&'static str, &'static str, &'static str)> {
+    BUILTINS.iter().find(|x| x.0 == name)
+}
+
+/// Operators are parsed as function calls too but their name isn't at the start of the span.
+fn is_named_call(fc: &FunctionCall) -> bool {
+    fc.span
+        .span
+        .as_str()
+        .strip_prefix(fc.name.as_str())
+        .map(|x| x.trim_start().starts_with('('))
+        .unwrap_or(false)
+}
+
+fn diagnostic(file_name: &str, content: &str, error: &CError) -> Diagnostic {
+    let error = error.as_pest_error();
+    let message = error.variant.message().into_owned();
+    let location = error.locations.iter().find(|x| x.path() == Some(file_name));
+    let position = |(line, column): (usize, usize)| {
+        let text = content.lines().nth(line - 1).unwrap_or_default();
+        (line - 1, utf16_column(text, column))
+    };
+    let range = match location.map(|x| &x.line_col) {
+        Some(LineColLocation::Pos(a)) => Range {
+            start: position(*a),
+            end: position(*a),
+        },
+        Some(LineColLocation::Span(a, b)) => Range {
+            start: position(*a),
+            end: position(*b),
+        },
+        None => Range::default(),
+    };
+    let message = match error.locations.first() {
+        Some(e) if e.path() != Some(file_name) => {
+            format!("{} (in `{}`)", message, e.path().unwrap_or_default())
+        }
+        _ => message,
+    };
+    Diagnostic { range, message }
+}
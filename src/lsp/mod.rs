@@ -0,0 +1,295 @@
+//! A small CH2L language server speaking the language server protocol over stdio.
+//! It reuses the compiler front-end to report errors, and the parsed files to resolve
+//! definitions.
+
+pub mod analysis;
+mod transport;
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use self::analysis::{builtin, Analysis, DefinitionKind, Range};
+
+/// How long the server waits without messages before checking the edited documents again.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    /// The analyses of the documents since the last change, a document can include the others.
+    analyses: HashMap<String, Analysis>,
+    /// The documents edited since their diagnostics were published.
+    edited: BTreeSet<String>,
+    exit: bool,
+}
+
+pub fn run() {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+        while let Some(message) = transport::read_message(&mut input) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    let stdout = std::io::stdout();
+    let mut output = stdout.lock();
+    let mut server = Server::default();
+
+    loop {
+        let messages = match receiver.recv_timeout(DEBOUNCE) {
+            Ok(e) => server.handle(&e),
+            Err(RecvTimeoutError::Timeout) => server.publish_edited(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        for i in messages {
+            if transport::write_message(&mut output, &i).is_err() {
+                return;
+            }
+        }
+        if server.exit {
+            break;
+        }
+    }
+}
+
+impl Server {
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": { "name": "cyc" },
+            }),
+            "shutdown" => Value::Null,
+            "exit" => {
+                self.exit = true;
+                return vec![];
+            }
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri_to_path(uri), text.to_owned());
+                self.analyses.clear();
+                return vec![self.diagnostics(uri)];
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|x| x.last())
+                    .and_then(|x| x["text"].as_str())
+                {
+                    self.documents.insert(uri_to_path(uri), text.to_owned());
+                    self.analyses.clear();
+                }
+                // The diagnostics are published once the user stops typing
+                self.edited.insert(uri.to_owned());
+                return vec![];
+            }
+            "textDocument/didSave" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.edited.remove(uri);
+                return vec![self.diagnostics(uri)];
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(&uri_to_path(uri));
+                self.analyses.clear();
+                self.edited.remove(uri);
+                return vec![notification(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )];
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/completion" => self.completion(params),
+            _ => {
+                return match id {
+                    Some(id) => vec![json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32601, "message": format!("Unknown method `{}`", method) },
+                    })],
+                    None => vec![],
+                }
+            }
+        };
+        match id {
+            Some(id) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => vec![],
+        }
+    }
+
+    /// Publishes the diagnostics of the documents edited since the last call.
+    pub fn publish_edited(&mut self) -> Vec<Value> {
+        std::mem::take(&mut self.edited)
+            .iter()
+            .map(|x| self.diagnostics(x))
+            .collect()
+    }
+
+    /// Analyses the document, the analysis is kept until a document changes.
+    fn analyze(&mut self, uri: &str) -> Option<(String, &Analysis)> {
+        let path = uri_to_path(uri);
+        let content = self.documents.get(&path)?;
+        if !self.analyses.contains_key(&path) {
+            let analysis = Analysis::new(&path, content, &self.documents);
+            self.analyses.insert(path.clone(), analysis);
+        }
+        let analysis = &self.analyses[&path];
+        Some((path, analysis))
+    }
+
+    fn diagnostics(&mut self, uri: &str) -> Value {
+        let diagnostics = self
+            .analyze(uri)
+            .map(|(_, x)| {
+                x.diagnostics
+                    .iter()
+                    .map(|x| {
+                        json!({
+                            "range": range(&x.range),
+                            "severity": 1,
+                            "source": "cyc",
+                            "message": x.message,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+
+    fn definition(&mut self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let (_, analysis) = match self.analyze(uri) {
+            Some(e) => e,
+            None => return Value::Null,
+        };
+        match analysis.reference_at(position(params)) {
+            Some(analysis::Reference::Call(name, _)) => match analysis.definition(name) {
+                Some(e) => json!({ "uri": path_to_uri(&e.file), "range": range(&e.range) }),
+                None => Value::Null,
+            },
+            Some(analysis::Reference::Include(file, _)) => {
+                json!({ "uri": path_to_uri(file), "range": range(&Range::default()) })
+            }
+            None => Value::Null,
+        }
+    }
+
+    fn hover(&mut self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match self
+            .analyze(uri)
+            .and_then(|(_, x)| x.hover(position(params)))
+        {
+            Some(e) => json!({ "contents": { "kind": "markdown", "value": e } }),
+            None => Value::Null,
+        }
+    }
+
+    fn completion(&mut self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let (_, analysis) = match self.analyze(uri) {
+            Some(e) => e,
+            None => return json!([]),
+        };
+        Value::Array(
+            analysis
+                .functions
+                .iter()
+                .map(|name| match analysis.definition(name) {
+                    Some(e) => json!({
+                        "label": name,
+                        "kind": if matches!(e.kind, DefinitionKind::Struct(_)) { 22 } else { 3 },
+                        "detail": e.signature(),
+                        "documentation": { "kind": "markdown", "value": e.documentation() },
+                    }),
+                    None => match builtin(name) {
+                        Some((_, a, b)) => json!({
+                            "label": name,
+                            "kind": 3,
+                            "detail": a,
+                            "documentation": b,
+                        }),
+                        None => json!({ "label": name, "kind": 3 }),
+                    },
+                })
+                .collect(),
+        )
+    }
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn position(params: &Value) -> (usize, usize) {
+    (
+        params["position"]["line"].as_u64().unwrap_or_default() as usize,
+        params["position"]["character"].as_u64().unwrap_or_default() as usize,
+    )
+}
+
+fn range(range: &Range) -> Value {
+    json!({
+        "start": { "line": range.start.0, "character": range.start.1 },
+        "end": { "line": range.end.0, "character": range.end.1 },
+    })
+}
+
+fn uri_to_path(uri: &str) -> String {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    // Windows paths are sent as `file:///C:/...`
+    let path = match path.as_bytes() {
+        [b'/', _, b':', ..] => &path[1..],
+        _ => path,
+    };
+    let mut out = Vec::new();
+    let mut bytes = path.bytes();
+    while let Some(c) = bytes.next() {
+        if c == b'%' {
+            let hex: String = bytes.by_ref().take(2).map(|x| x as char).collect();
+            if let Ok(e) = u8::from_str_radix(&hex, 16) {
+                out.push(e);
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn path_to_uri(path: &str) -> String {
+    let path = std::fs::canonicalize(path)
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_owned())
+        .replace('\\', "/")
+        .replace(' ', "%20");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
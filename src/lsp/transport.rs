@@ -0,0 +1,40 @@
+use std::io::{BufRead, Write};
+
+use serde_json::Value;
+
+/// Reads a single `Content-Length` framed JSON-RPC message.
+/// Returns `None` once the client closed the stream.
+pub fn read_message(input: &mut impl BufRead) -> Option<Value> {
+    loop {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            if input.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(e) = line.strip_prefix("Content-Length:") {
+                length = e.trim().parse::<usize>().ok();
+            }
+        }
+        // A header block without a length can't be recovered from, skip it.
+        let length = match length {
+            Some(e) => e,
+            None => continue,
+        };
+        let mut body = vec![0; length];
+        input.read_exact(&mut body).ok()?;
+        if let Ok(e) = serde_json::from_slice(&body) {
+            return Some(e);
+        }
+    }
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> std::io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
@@ -14,7 +14,13 @@ mod executable;
 
 mod bit_utils;
 
+mod fmt;
+mod fuzz;
+mod lsp;
+mod test_runner;
+
 use std::{
+    path::PathBuf,
     process::exit,
     sync::{Arc, Mutex},
 };
@@ -28,6 +34,7 @@ use compiler::{
     asm::CompilableInstruction,
     error::{CError, CErrorType, CSpan},
     parser::{codeblock::CodeBlock, parse_file},
+    resolver::{env_paths, read_source},
     scope::ScopedState,
     state::State,
 };
@@ -45,25 +52,55 @@ pub enum ExportFormat {
     Binary,
 }
 
+pub enum Command {
+    Compile(String, String, ExportFormat, u8, Vec<PathBuf>),
+    Format(Vec<String>, bool),
+    Test(String, u8, Vec<PathBuf>),
+    Fuzz(usize, u64),
+    Lsp,
+}
+
 pub fn show_usage() {
     println!("Usages:");
-    println!("   cyc run <INPUT FILENAME> [Optional: base, Default: 16]");
+    println!("   cyc run <INPUT FILENAME> [Optional: base, Default: 4]");
     println!("   cyc build <INPUT FILENAME> <OUTPUT FILENAME> <TYPE> [Optional: base, Default: 4]");
     println!("    TYPE: V3, Bytecode, Binary, Default");
+    println!(
+        "    -I <DIRECTORY> adds a directory searched by include and import (also CYTHAN_PATH)"
+    );
+    println!(
+        "    include(std) is only bundled in base 4, other bases need a std/base<base>.ct1 file"
+    );
+    println!("   cyc test <INPUT FILENAME> [Optional: base, Default: 4]");
+    println!("   cyc fuzz [Optional: runs, Default: 1000] [Optional: seed, Default: random]");
+    println!("   cyc fmt [--check] <INPUT FILENAMES...>");
+    println!("   cyc lsp");
 }
 
-fn parse() -> Option<(String, String, ExportFormat, u8)> {
-    let mut args = std::env::args();
-    args.next()?;
+fn parse() -> Option<Command> {
+    let mut include_paths = Vec::new();
+    let mut others = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(e) = args.next() {
+        if e == "-I" {
+            include_paths.push(PathBuf::from(args.next()?));
+        } else if let Some(e) = e.strip_prefix("-I") {
+            include_paths.push(PathBuf::from(e));
+        } else {
+            others.push(e);
+        }
+    }
+    let mut args = others.into_iter();
 
     match args.next()?.as_str() {
-        "run" => Some((
+        "run" => Some(Command::Compile(
             args.next()?,
             String::new(),
             ExportFormat::Run,
             args.next().map(|x| x.parse().unwrap()).unwrap_or(4),
+            include_paths,
         )),
-        "build" => Some((
+        "build" => Some(Command::Compile(
             args.next()?,
             args.next()?,
             match args.next()?.to_lowercase().as_str() {
@@ -74,7 +111,30 @@ fn parse() -> Option<(String, String, ExportFormat, u8)> {
                 _ => return None,
             },
             args.next().map(|x| x.parse().unwrap()).unwrap_or(4),
+            include_paths,
+        )),
+        "fmt" => {
+            let (check, files): (Vec<String>, Vec<String>) = args.partition(|x| x == "--check");
+            if files.is_empty() {
+                return None;
+            }
+            Some(Command::Format(files, !check.is_empty()))
+        }
+        "test" => Some(Command::Test(
+            args.next()?,
+            args.next().map(|x| x.parse().unwrap()).unwrap_or(4),
+            include_paths,
         )),
+        "fuzz" => Some(Command::Fuzz(
+            args.next().map(|x| x.parse().unwrap()).unwrap_or(1000),
+            args.next().map(|x| x.parse().unwrap()).unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|x| x.as_secs())
+                    .unwrap_or(0)
+            }),
+        )),
+        "lsp" => Some(Command::Lsp),
         _ => None,
     }
 }
@@ -83,15 +143,30 @@ fn main() {
     /* let format = ExportFormat::Run;
     let out = "out.ct"; */
 
-    let (input, out, format, base) = if let Some(e) = parse() {
-        e
-    } else {
-        show_usage();
-        exit(-2);
+    let (input, out, format, base, include_paths) = match parse() {
+        Some(Command::Compile(input, out, format, base, include_paths)) => {
+            (input, out, format, base, include_paths)
+        }
+        Some(Command::Format(files, check)) => exit(fmt::run(&files, check)),
+        Some(Command::Test(file, base, mut include_paths)) => {
+            include_paths.extend(env_paths());
+            exit(test_runner::run(&file, base, include_paths))
+        }
+        Some(Command::Fuzz(runs, seed)) => exit(fuzz::run(runs, seed)),
+        Some(Command::Lsp) => {
+            lsp::run();
+            return;
+        }
+        None => {
+            show_usage();
+            exit(-2);
+        }
     };
 
     let mut state = State::default();
     state.base = base;
+    state.include_paths = include_paths;
+    state.include_paths.extend(env_paths());
     let mut scope = ScopedState::new();
 
     if let Err(e) = execute_file(&input, &mut state, &mut scope, vec![]) {
@@ -106,27 +181,32 @@ fn main() {
                 panic!()
             }
         }
-        ExportFormat::ByteCode => {
-            let mut mrstate = MirState::default();
-
-            MirCodeBlock(optimizer::opt(state.instructions.0)).to_asm(&mut mrstate);
-
-            std::fs::write(
-                out,
-                mrstate
-                    .instructions
-                    .iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-            )
-            .unwrap();
-        }
-        ExportFormat::CythanV3 => {
-            let mut k = MirState::default();
-            MirCodeBlock(optimizer::opt(state.instructions.0.clone())).to_asm(&mut k);
-            std::fs::write(out, compile_v3(opt_asm(k.instructions), state.base)).unwrap();
-        }
+        ExportFormat::ByteCode => match lower(&state) {
+            Ok(e) => {
+                std::fs::write(
+                    out,
+                    e.instructions
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+                .unwrap();
+            }
+            Err(e) => {
+                println!("{}", e);
+                exit(-3);
+            }
+        },
+        ExportFormat::CythanV3 => match lower(&state) {
+            Ok(e) => {
+                std::fs::write(out, compile_v3(opt_asm(e.instructions), state.base)).unwrap();
+            }
+            Err(e) => {
+                println!("{}", e);
+                exit(-3);
+            }
+        },
         ExportFormat::Cythan => match compile(&state) {
             Ok(e) => {
                 std::fs::write(
@@ -192,7 +272,7 @@ pub fn compile_and_run(state: &State, inputs: Vec<char>) -> Result<String> {
         move |a| {
             string.lock().unwrap().push(a as char);
         },
-        move || k.lock().unwrap().next().unwrap() as u8,
+        move || k.lock().unwrap().next().map(|x| x as u8),
     );
 
     loop {
@@ -218,9 +298,15 @@ pub fn compile_binary(state: &State) -> Result<Vec<u8>> {
     }))
 }
 
-pub fn compile(state: &State) -> Result<Vec<usize>> {
+/// Optimizes the MIR of the program and lowers it to assembly instructions
+fn lower(state: &State) -> Result<MirState> {
     let mut k = MirState::default();
-    MirCodeBlock(optimizer::opt(state.instructions.0.clone())).to_asm(&mut k);
+    MirCodeBlock(optimizer::opt(state.instructions.0.clone(), state.base)).to_asm(&mut k)?;
+    Ok(k)
+}
+
+pub fn compile(state: &State) -> Result<Vec<usize>> {
+    let k = lower(state)?;
     cythan_compiler::compile(&compile_v3(opt_asm(k.instructions), state.base))
         .map_err(|e| e.to_string())
         .map_err(|e| CError(vec![], CErrorType::InternalCompilerError(e)))
@@ -241,16 +327,28 @@ pub fn execute_file(
     scope: &mut ScopedState,
     span: Vec<CSpan>,
 ) -> Result<()> {
-    CodeBlock(parse_file(
+    execute_source(
         file_name,
-        match std::fs::read_to_string(file_name) {
-            Ok(a) => a,
-            Err(_e) => {
+        match read_source(file_name) {
+            Some(a) => a,
+            None => {
                 return Err(CError(span, CErrorType::FileNotFound(file_name.to_owned())));
             }
         },
+        state,
+        scope,
         span,
-    )?)
-    .execute_with_scope(state, scope)
-    .map(|_| ())
+    )
+}
+
+pub fn execute_source(
+    file_name: &str,
+    file_content: String,
+    state: &mut State,
+    scope: &mut ScopedState,
+    span: Vec<CSpan>,
+) -> Result<()> {
+    CodeBlock(parse_file(file_name, file_content, span)?)
+        .execute_with_scope(state, scope)
+        .map(|_| ())
 }
@@ -97,6 +97,22 @@ impl<'a> Template<'a> {
                 .collect::<Vec<_>>()
                 .join(" ")
         )));
+        // Writes the address of each case in the cell of its value (0 is the last one) and
+        // jumps to the address in the cell of the value
+        this.add_section(
+            "V3_FCT_DEF",
+            Cow::Owned(format!(
+                "switch {{ self.0 'test {} 'test:earasable 0 {} }}",
+                (0..number_of_eles)
+                    .map(|x| format!("'c{} {}", x, if x == 0 { number_of_eles } else { x }))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                (0..number_of_eles)
+                    .map(|x| format!("'c{}:self.{}", x, x + 1))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )),
+        );
         this.add_section("V3_FCT_DEF", 
         Cow::Owned(format!(
             "if_not0 {{ self.0 'test 'end {} 1 'test:earasable 0 jump('end1) 'pt:self.1 'end:~+1 'end1:no_op }}",
@@ -0,0 +1,143 @@
+//! `cyc test <file>` finds the `test` blocks of a file, compiles each of them in a program of its
+//! own and runs it on the virtual machine.
+
+use std::path::PathBuf;
+
+use cythan::{Cythan, InterruptedCythan};
+
+use crate::{
+    compile,
+    compiler::{
+        asm::{AsmValue, Number},
+        error::{CError, CErrorType, CSpan},
+        functions::{fn_array::OUT_OF_BOUNDS, fn_assert::ASSERT_FAILED},
+        mir::Mir,
+        scope::ScopedState,
+        state::State,
+        type_defs::Result,
+    },
+    execute_file,
+    template::get_interrupt_pos_from_base,
+};
+
+/// A test taking more steps is stopped, it is probably in an infinite loop
+const MAX_STEPS: usize = 10_000_000;
+
+pub enum Outcome {
+    Passed,
+    /// The position of the failed assert
+    Failed(CSpan),
+    /// The test stopped with `exit` or ended with another code than 0
+    Exited(u8),
+    /// The test read or wrote an array out of its bounds
+    OutOfBounds,
+    TimedOut,
+}
+
+pub fn run(file: &str, base: u8, include_paths: Vec<PathBuf>) -> i32 {
+    let results = match run_tests(file, base, &include_paths) {
+        Ok(e) => e,
+        Err(e) => {
+            println!("{}", e);
+            return 1;
+        }
+    };
+    let mut failed = 0;
+    for (name, outcome) in &results {
+        match outcome {
+            Ok(Outcome::Passed) => println!("test {} ... ok", name),
+            Ok(Outcome::Failed(span)) => {
+                println!("test {} ... FAILED", name);
+                println!(
+                    "{}",
+                    CError(vec![span.clone()], CErrorType::AssertionFailed)
+                );
+            }
+            Ok(Outcome::Exited(code)) => {
+                println!("test {} ... FAILED", name);
+                println!("The test exited with the code {}", code);
+            }
+            Ok(Outcome::OutOfBounds) => {
+                println!("test {} ... FAILED", name);
+                println!("An array was indexed out of its bounds");
+            }
+            Ok(Outcome::TimedOut) => println!("test {} ... timed out", name),
+            Err(e) => {
+                println!("test {} ... FAILED", name);
+                println!("{}", e);
+            }
+        }
+        if !matches!(outcome, Ok(Outcome::Passed)) {
+            failed += 1;
+        }
+    }
+    println!("{} passed, {} failed", results.len() - failed, failed);
+    (failed != 0) as i32
+}
+
+/// Runs every test of the file, the error is returned if the file doesn't compile.
+pub fn run_tests(
+    file: &str,
+    base: u8,
+    include_paths: &[PathBuf],
+) -> Result<Vec<(String, Result<Outcome>)>> {
+    let names = build(file, base, include_paths, None)?.tests;
+    Ok(names
+        .into_iter()
+        .map(|(_, name)| {
+            let outcome = build(file, base, include_paths, Some(name.clone()))
+                .and_then(|state| execute(&state));
+            (name, outcome)
+        })
+        .collect())
+}
+
+fn build(file: &str, base: u8, include_paths: &[PathBuf], test: Option<String>) -> Result<State> {
+    let mut state = State::default();
+    state.base = base;
+    state.include_paths = include_paths.to_vec();
+    state.test = test;
+    execute_file(file, &mut state, &mut ScopedState::new(), vec![])?;
+    // A test reaching its end exits with 0, whatever the code printed or read before
+    if state.test.is_some() {
+        state
+            .instructions
+            .push(Mir::WriteRegister(Number(0), AsmValue::Number(Number(0))));
+    }
+    Ok(state)
+}
+
+fn execute(state: &State) -> Result<Outcome> {
+    let interrupt = get_interrupt_pos_from_base(state.base);
+    // The output of the tests isn't shown and their input is empty
+    let mut machine =
+        InterruptedCythan::new(compile(state)?, state.base, interrupt, |_| (), || None);
+    for _ in 0..MAX_STEPS / 1000 {
+        for _ in 0..1000 {
+            machine.next();
+        }
+        let o = machine.cases.clone();
+        machine.next();
+        if o == machine.cases {
+            let code = machine.get_value(interrupt) as u8;
+            let index = read_registers(&machine, state.base, interrupt);
+            return Ok(match state.asserts.get(index as usize) {
+                _ if code == 0 => Outcome::Passed,
+                Some(e) if code == ASSERT_FAILED => Outcome::Failed(e.clone()),
+                _ if code == ASSERT_FAILED && index == OUT_OF_BOUNDS => Outcome::OutOfBounds,
+                _ => Outcome::Exited(code),
+            });
+        }
+    }
+    Ok(Outcome::TimedOut)
+}
+
+/// Reads the registers like the machine does to print a character
+fn read_registers(machine: &InterruptedCythan, base: u8, interrupt: usize) -> u8 {
+    let digit = 2_usize.pow(base as u32);
+    let rest = 2_usize.pow(8 % base as u32);
+    let high = (1..=8 / base as usize).fold(0, |x, n| {
+        x * digit + machine.get_value(interrupt + n) % digit
+    });
+    (high * rest + machine.get_value(interrupt + 8 / base as usize + 2) % rest) as u8
+}
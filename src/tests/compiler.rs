@@ -10,12 +10,19 @@ struct Test {
     output: Option<String>,
     error: Option<String>,
     base: Option<u8>,
+    include_paths: Option<Vec<String>>,
 }
 
 impl Test {
     fn run_test(&self) {
         let mut state = State::default();
         state.base = self.base.unwrap_or(4);
+        state.include_paths = self
+            .include_paths
+            .iter()
+            .flatten()
+            .map(|x| x.into())
+            .collect();
         let mut scope = ScopedState::new();
 
         if let Err(e) = execute_file(
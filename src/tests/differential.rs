@@ -0,0 +1,118 @@
+//! Runs every program both with its unoptimised MIR interpreted and with its optimised code
+//! compiled and run on `InterruptedCythan`: the output and the exit code must be the same.
+//! The optimised MIR is also interpreted, so a difference points to the optimizer or to the
+//! code generation.
+
+use crate::{
+    compiler::{mir::optimizer, scope::ScopedState, state::State},
+    execute_file,
+    fuzz::{interpret, run_compiled},
+};
+
+/// Examples kept for reference which don't compile anymore, they use `@>` without the std
+const BROKEN: &[&str] = &["examples/helloWorld.ct1", "examples/print.ct1"];
+
+/// Programs which don't stop in `fuzz::MAX_STEPS` so they can't be compared, the `while0` loop of
+/// the game sets its condition back to 0
+const ENDLESS: &[&str] = &["examples/morpion.ct1"];
+
+#[derive(serde::Deserialize)]
+struct Program {
+    file: String,
+    input: Option<String>,
+    error: Option<String>,
+    base: Option<u8>,
+    include_paths: Option<Vec<String>>,
+}
+
+fn programs() -> Vec<Program> {
+    let mut programs = Vec::new();
+    for i in std::fs::read_dir("src/tests/cythan_tests").unwrap() {
+        let path = i.unwrap().path();
+        if matches!(path.extension(), Some(x) if x == "json") {
+            let tests: Vec<Program> =
+                serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            programs.extend(
+                tests
+                    .into_iter()
+                    .filter(|x| x.error.is_none())
+                    .map(|x| Program {
+                        file: format!("src/tests/cythan_tests/{}.ct1", x.file),
+                        ..x
+                    }),
+            );
+        }
+    }
+    for i in std::fs::read_dir("examples").unwrap() {
+        let path = i.unwrap().path();
+        if matches!(path.extension(), Some(x) if x == "ct1") {
+            programs.push(Program {
+                file: path.to_string_lossy().into_owned(),
+                input: None,
+                error: None,
+                base: None,
+                include_paths: None,
+            });
+        }
+    }
+    programs
+}
+
+#[test]
+fn optimized_code_behaves_like_mir() {
+    for program in programs() {
+        let mut state = State::default();
+        state.base = program.base.unwrap_or(4);
+        state.include_paths = program
+            .include_paths
+            .iter()
+            .flatten()
+            .map(|x| x.into())
+            .collect();
+        let result = execute_file(&program.file, &mut state, &mut ScopedState::new(), vec![]);
+        if BROKEN.contains(&program.file.as_str()) {
+            assert!(
+                result.is_err(),
+                "{} compiles, remove it from `BROKEN`",
+                program.file
+            );
+            continue;
+        }
+        if let Err(e) = result {
+            panic!("{} doesn't compile\n{}", program.file, e);
+        }
+        let input = program.input.clone().unwrap_or_default();
+        let reference = interpret(state.instructions.0.clone(), state.base, &input);
+        if ENDLESS.contains(&program.file.as_str()) {
+            assert!(
+                reference.is_none(),
+                "{} stops, remove it from `ENDLESS`",
+                program.file
+            );
+            continue;
+        }
+        assert!(
+            reference.is_some(),
+            "{} doesn't stop when interpreted with the input {:?}",
+            program.file,
+            input
+        );
+        let optimized = optimizer::opt(state.instructions.0.clone(), state.base);
+        assert_eq!(
+            interpret(optimized, state.base, &input),
+            reference,
+            "The optimizer changed the behaviour of {} with the input {:?}",
+            program.file,
+            input
+        );
+        let compiled = match run_compiled(&state, &input) {
+            Ok(e) => e,
+            Err(e) => panic!("{}", e),
+        };
+        assert_eq!(
+            compiled, reference,
+            "The compiled code of {} with the input {:?} behaves differently from its MIR",
+            program.file, input
+        );
+    }
+}
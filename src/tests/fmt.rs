@@ -0,0 +1,107 @@
+use crate::{
+    compiler::parser::cst::{Cst, CstNode},
+    fmt::format_source,
+};
+
+fn sources() -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut dirs = vec![
+        std::path::PathBuf::from("examples"),
+        std::path::PathBuf::from("src/tests/cythan_tests"),
+    ];
+    while let Some(dir) = dirs.pop() {
+        for i in std::fs::read_dir(dir).unwrap() {
+            let path = i.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().map(|x| x == "ct1").unwrap_or(false) {
+                let name = path.to_str().unwrap().to_owned();
+                out.push((name, std::fs::read_to_string(path).unwrap()));
+            }
+        }
+    }
+    out
+}
+
+/// The nodes without children, in the order of the source
+fn leaves<'a>(node: &'a CstNode, out: &mut Vec<&'a CstNode>) {
+    if node.children.is_empty() {
+        out.push(node);
+    }
+    for i in &node.children {
+        leaves(i, out);
+    }
+}
+
+/// The leaves of the tree and the trivia between them (whitespace and punctuation) are the whole
+/// source, so no token is lost nor written twice.
+#[test]
+fn cst_is_lossless() {
+    for (name, source) in sources() {
+        let cst = Cst::parse(&name, source.clone(), vec![]).ok().unwrap();
+        let mut tokens = Vec::new();
+        leaves(&cst.root, &mut tokens);
+        let mut rebuilt = String::new();
+        let mut pos = 0;
+        for i in tokens
+            .iter()
+            .map(|x| (x.start, x.end))
+            .chain([(source.len(), source.len())])
+        {
+            assert!(
+                pos <= i.0,
+                "{}: the token at {} overlaps the previous one",
+                name,
+                i.0
+            );
+            let trivia = &source[pos..i.0];
+            assert!(
+                trivia
+                    .chars()
+                    .all(|x| x.is_whitespace() || "(),;{}:".contains(x)),
+                "{}: `{}` at {} isn't in the tree",
+                name,
+                trivia,
+                pos
+            );
+            rebuilt.push_str(trivia);
+            rebuilt.push_str(&source[i.0..i.1]);
+            pos = i.1;
+        }
+        assert_eq!(rebuilt, source, "{}", name);
+        assert_eq!(cst.to_source(), source, "{}", name);
+    }
+}
+
+#[test]
+fn format_is_idempotent() {
+    for (name, source) in sources() {
+        let once = format_source(&name, source).ok().unwrap();
+        let twice = format_source(&name, once.clone()).ok().unwrap();
+        assert_eq!(once, twice, "{}", name);
+    }
+}
+
+#[test]
+fn format_keeps_leading_comments() {
+    let source = "fn( ((c)) a, b);\nf( ((c))  ((d)) );\n";
+    let formatted = format_source("test.ct1", source.to_owned()).ok().unwrap();
+    assert_eq!(formatted, "fn( ((c)) a, b);\nf( ((c)) ((d)));\n");
+    assert_eq!(
+        format_source("test.ct1", formatted.clone()).ok().unwrap(),
+        formatted
+    );
+}
+
+#[test]
+fn format_canonical() {
+    let source = "(( Counter ))\nfn(cls,&*a,{set(a,0);a})\nfn(+=,&*a,&b,{a = a + b}) (( add ))\n\n\n\nloop({if0(a,{break()});\n   dec(a)})";
+    assert_eq!(
+        format_source("test.ct1", source.to_owned()).ok().unwrap(),
+        "(( Counter ))\n\
+         fn(cls, &*a, {\n    set(a, 0);\n    a;\n});\n\
+         fn(+=, &*a, &b, :a = a + b); (( add ))\n\
+         \n\
+         loop({\n    if0(a, :break());\n    dec(a);\n});\n"
+    );
+}
@@ -0,0 +1,77 @@
+use proptest::prelude::*;
+
+use crate::{
+    compiler::{scope::ScopedState, state::State},
+    execute_source,
+    fuzz::{check, generate},
+    lower,
+};
+
+/// The pieces the programs of `front_end_doesnt_panic` are made of, with spaces and new lines.
+/// They are mostly valid tokens so the programs get past the parser.
+const TOKENS: &str =
+    "set get inc dec if0 loop break continue exit print fn lambda struct get_field \
+    set_field array len const match a b Point ( ) , ; { } : & $ ... = + - * == < . 0 1 15 16 300 \
+    'a' \"b\"";
+
+fn token() -> impl Strategy<Value = &'static str> {
+    let mut tokens: Vec<&str> = TOKENS.split_whitespace().collect();
+    tokens.extend([" ", "\n"]);
+    prop::sample::select(tokens)
+}
+
+#[test]
+fn generated_programs_pass() {
+    for seed in 0..50 {
+        let program = generate(seed);
+        if let Err(e) = check(&program) {
+            panic!("The program {} failed: {}\n{}", seed, e, program);
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn programs_of_any_seed_pass(seed in any::<u64>()) {
+        let program = generate(seed);
+        if let Err(e) = check(&program) {
+            panic!("The program {} failed: {}\n{}", seed, e, program);
+        }
+    }
+
+    /// The front-end and the lowering give an error on invalid programs, they never panic
+    #[test]
+    fn front_end_doesnt_panic(tokens in prop::collection::vec(token(), 0..40)) {
+        let mut state = State::default();
+        state.base = 4;
+        let result = execute_source(
+            "fuzz.ct1",
+            tokens.concat(),
+            &mut state,
+            &mut ScopedState::new(),
+            vec![],
+        )
+        .and_then(|_| lower(&state));
+        // Displaying some errors used to panic too
+        if let Err(e) = result {
+            let _ = e.to_string();
+        }
+    }
+
+    #[test]
+    fn parser_doesnt_panic(source in "\\PC*") {
+        let mut state = State::default();
+        let result = execute_source(
+            "fuzz.ct1",
+            source,
+            &mut state,
+            &mut ScopedState::new(),
+            vec![],
+        );
+        if let Err(e) = result {
+            let _ = e.to_string();
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::lsp::{
+    analysis::{utf16_column, Analysis, Range},
+    Server,
+};
+
+use super::panics::PROGRAMS;
+
+fn analyze(content: &str) -> Analysis {
+    Analysis::new("lsp.ct1", content, &HashMap::new())
+}
+
+#[test]
+fn diagnostics() {
+    let analysis = analyze("set(a, 1);\nfoo(a);\n");
+    let diagnostics: Vec<(Range, &str)> = analysis
+        .diagnostics
+        .iter()
+        .map(|x| (x.range, x.message.as_str()))
+        .collect();
+    assert_eq!(
+        diagnostics,
+        vec![(
+            Range {
+                start: (1, 0),
+                end: (1, 6),
+            },
+            "Function `foo` not found"
+        )]
+    );
+    assert!(analyze("set(a, 1);\nexit(a);\n").diagnostics.is_empty());
+}
+
+#[test]
+fn hover() {
+    let analysis = analyze("fn(double, a, :a + a);\nexit(double(2));\n");
+    assert_eq!(
+        analysis.hover((1, 6)).as_deref(),
+        Some("```\nfn(double, a)\n```\n- `a` copy of the input value")
+    );
+    assert_eq!(
+        analysis.hover((1, 1)).as_deref(),
+        Some("```\nexit(<number>)\n```\nExits the program with the given code")
+    );
+    assert_eq!(analysis.hover((0, 20)), None);
+}
+
+#[test]
+fn definition() {
+    let analysis = analyze("fn(double, a, :a + a);\nexit(double(2));\n");
+    let name = match analysis.reference_at((1, 8)) {
+        Some(crate::lsp::analysis::Reference::Call(e, _)) => e.clone(),
+        _ => panic!("`double` isn't a reference"),
+    };
+    let definition = analysis.definition(&name).expect("`double` isn't defined");
+    assert_eq!(definition.file, "lsp.ct1");
+    assert_eq!(
+        definition.range,
+        Range {
+            start: (0, 3),
+            end: (0, 9),
+        }
+    );
+}
+
+#[test]
+fn columns_are_utf16() {
+    assert_eq!(utf16_column("a😀é b", 1), 0);
+    assert_eq!(utf16_column("a😀é b", 3), 3);
+    assert_eq!(utf16_column("a😀é b", 5), 5);
+    // The comment holds a character outside of the basic multilingual plane
+    let analysis = analyze("(( é😀 )) fn(f, :1);\nexit(f());\n(( 😀 )) foo(1);\n");
+    assert_eq!(
+        analysis.definition("f").map(|x| x.range),
+        Some(Range {
+            start: (0, 13),
+            end: (0, 14),
+        })
+    );
+    assert_eq!(
+        analysis.diagnostics.first().map(|x| x.range.start),
+        Some((2, 9))
+    );
+}
+
+#[test]
+fn edits_are_debounced() {
+    let mut server = Server::default();
+    let document = |method: &str, text: &str| {
+        json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": {
+                "textDocument": { "uri": "file:///lsp.ct1", "text": text },
+                "contentChanges": [{ "text": text }],
+            },
+        })
+    };
+    let messages = |x: &[Value]| -> Vec<usize> {
+        x.iter()
+            .map(|x| x["params"]["diagnostics"].as_array().map_or(0, Vec::len))
+            .collect()
+    };
+    let opened = server.handle(&document("textDocument/didOpen", "exit(1);"));
+    assert_eq!(messages(&opened), vec![0]);
+    assert!(server
+        .handle(&document("textDocument/didChange", "foo("))
+        .is_empty());
+    assert!(server
+        .handle(&document("textDocument/didChange", "foo(1);"))
+        .is_empty());
+    assert_eq!(messages(&server.publish_edited()), vec![1]);
+    assert!(server.publish_edited().is_empty());
+}
+
+#[test]
+fn analysis_doesnt_panic() {
+    for (program, _) in PROGRAMS {
+        analyze(&format!("include(std);\n{}\n", program));
+    }
+}
@@ -1 +1,7 @@
 mod compiler;
+mod differential;
+mod fmt;
+mod fuzz;
+mod lsp;
+mod panics;
+mod test_runner;
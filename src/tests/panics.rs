@@ -0,0 +1,99 @@
+//! Programs which used to make the compiler panic, they must compile or give the expected error.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::{
+    compile,
+    compiler::{
+        mir::{Mir, MirCodeBlock, MirState},
+        scope::ScopedState,
+        state::State,
+    },
+    execute_source,
+};
+
+/// The programs with the position and the message of their error, or `ok` if they compile. They
+/// are written after `include(std);`, so they start on the line 2.
+pub const PROGRAMS: &[(&str, &str)] = &[
+    // A parse error displayed without its position
+    ("a = (;", "2:6 expected COMMENT, literal, or expression"),
+    ("a = 1 + + 2;", "2:9 Function `+_unique` not found"),
+    ("a = \"\\x\";", "2:5 Invalid escape `\\x`"),
+    // A copy of a variable to itself, the optimizer followed the reference forever
+    (
+        "set(a, 1); set(b, 2); set(c, 2); loop({ if0(c, :break()); dec(c); set(a, b); set(b, a); inc(b); }); exit(b);",
+        "ok",
+    ),
+    ("set(a, 1); set(b, a); set(a, b); exit(a);", "ok"),
+    ("exit();", "2:1 Invalid number of argument. Expected 1 arguments"),
+    ("fn(f);", "2:1 Invalid number of argument. Expected 2 arguments"),
+    ("lambda();", "2:1 Invalid number of argument. Expected 1 arguments"),
+    ("match(1);", "ok"),
+    ("break();", "2:1 Can't break or continue outside of a loop"),
+    ("continue();", "2:1 Can't break or continue outside of a loop"),
+    ("a = 1; a(1);", "2:8 Function `a` not found"),
+    ("fn(f, a, :f(a)); f(1);", "2:11 Function `f` not found"),
+    ("fn(f, ...a, b, :1); f(1);", "2:7 Only the last argument can be variadic and it can't have a default value"),
+    ("set(a, array(2)); set(a, 5, 1);", "2:26 Index 5 is out of bounds for an array of length 2"),
+    ("get_reg(a, 300);", "2:12 Invalid number"),
+    ("include(/);", "2:9 Can't read `/` file. Ensure that the path is correct"),
+    ("loop({ const(k, { break(); 1; }); break(); });", "ok"),
+    // A constant set to a variable known at runtime
+    ("x = 3; const(y, x);", "2:17 The value can't be computed when compiling, it uses a value only known at runtime"),
+];
+
+/// Compiles the program and gives `ok` or the position and the message of the error
+fn outcome(program: &str) -> String {
+    let mut state = State::default();
+    state.base = 4;
+    let source = format!("include(std);\n{}\n", program);
+    match execute_source(
+        "panics.ct1",
+        source,
+        &mut state,
+        &mut ScopedState::new(),
+        vec![],
+    )
+    .and_then(|_| compile(&state))
+    {
+        Ok(_) => "ok".to_owned(),
+        Err(e) => {
+            let error = e.as_pest_error();
+            let position = match error.locations.first().map(|x| &x.line_col) {
+                Some(pest::error::LineColLocation::Pos((a, b)))
+                | Some(pest::error::LineColLocation::Span((a, b), _)) => format!("{}:{}", a, b),
+                None => "-".to_owned(),
+            };
+            format!("{} {}", position, e.1)
+        }
+    }
+}
+
+#[test]
+fn programs_dont_panic() {
+    for (program, expected) in PROGRAMS {
+        let result = catch_unwind(AssertUnwindSafe(|| outcome(program)));
+        match result {
+            Ok(e) => assert_eq!(
+                &e, expected,
+                "The compiler gave another result on `{}`",
+                program
+            ),
+            Err(_) => panic!("The compiler panicked on `{}`", program),
+        }
+    }
+}
+
+#[test]
+fn lowering_checks_the_depth() {
+    for mir in [
+        Mir::Break(0),
+        Mir::Continue(0),
+        Mir::Exit(0),
+        Mir::Loop(MirCodeBlock(vec![Mir::Break(1)])),
+        Mir::Block(MirCodeBlock(vec![Mir::Exit(1)])),
+    ] {
+        let result = MirCodeBlock(vec![mir.clone()]).to_asm(&mut MirState::default());
+        assert!(result.is_err(), "`{}` was lowered", mir);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::test_runner::{run_tests, Outcome};
+
+#[test]
+fn test_blocks() {
+    let results = match run_tests("src/tests/cythan_tests/compiler/test_blocks.ct1", 4, &[]) {
+        Ok(e) => e,
+        Err(e) => panic!("{}", e),
+    };
+    let results: Vec<(&str, String)> = results
+        .iter()
+        .map(|(name, outcome)| match outcome {
+            Ok(Outcome::Passed) => (name.as_str(), "ok".to_owned()),
+            Ok(Outcome::Failed(e)) => (
+                name.as_str(),
+                format!("line {}", e.span.start_pos().line_col().0),
+            ),
+            Ok(Outcome::Exited(e)) => (name.as_str(), format!("exit {}", e)),
+            Ok(Outcome::OutOfBounds) => (name.as_str(), "out of bounds".to_owned()),
+            Ok(Outcome::TimedOut) => panic!("{} timed out", name),
+            Err(e) => panic!("{} doesn't compile: {}", name, e),
+        })
+        .collect();
+    assert_eq!(
+        results,
+        vec![
+            ("doubles", "ok".to_owned()),
+            ("wraps", "ok".to_owned()),
+            ("fails", "line 15".to_owned()),
+            ("exits", "exit 3".to_owned()),
+            ("exits_one", "exit 1".to_owned()),
+            ("prints", "ok".to_owned()),
+            ("out_of_bounds", "out of bounds".to_owned()),
+        ]
+    );
+}